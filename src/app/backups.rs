@@ -0,0 +1,534 @@
+use crate::{
+    backend::{BackupFile, BackupStatus, PackageBackend, TransactionEvent},
+    config::Colors,
+    utils::create_block,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
+};
+use strum_macros::{Display, FromRepr};
+
+/// Largest number of line pairs compared to align two files, above which they are shown
+/// line by line.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Lines scrolled at once in the diff.
+const DIFF_SCROLL_STEP: u16 = 10;
+
+/// What to do with a `.pacnew` or `.pacsave` file, like the `pacdiff` prompt.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, FromRepr)]
+pub enum LeftoverAction {
+    #[default]
+    #[strum(to_string = "merge")]
+    Merge,
+    #[strum(to_string = "replace")]
+    Replace,
+    #[strum(to_string = "delete")]
+    Delete,
+}
+
+impl LeftoverAction {
+    /// Get previous action.
+    pub fn previous(self) -> Self {
+        let current_index = self as usize;
+        let previous_index = current_index.saturating_sub(1);
+
+        Self::from_repr(previous_index).unwrap_or(self)
+    }
+
+    /// Get next action.
+    pub fn next(self) -> Self {
+        let current_index = self as usize;
+        let next_index = current_index.saturating_add(1);
+
+        Self::from_repr(next_index).unwrap_or(self)
+    }
+
+    /// Describe what the action does to the `leftover` of the backup file at `path`.
+    fn description(self, path: &str, leftover: &str) -> String {
+        match self {
+            Self::Merge => format!("edit /{path} and /{leftover} side by side with $DIFFPROG"),
+            Self::Replace => format!("move /{leftover} over /{path}"),
+            Self::Delete => format!("delete /{leftover}, keeping /{path}"),
+        }
+    }
+}
+
+/// Row of the list, one per leftover or per backup file without any.
+struct BackupRow {
+    backup: usize,
+    leftover: Option<usize>,
+}
+
+/// Line of the side by side diff, `None` on the side missing it.
+struct DiffRow {
+    old: Option<String>,
+    new: Option<String>,
+}
+
+/// Backup files of the installed packages and the `.pacnew` and `.pacsave` files left next
+/// to them, like `pacdiff`.
+#[derive(Default)]
+pub struct BackupWidget {
+    backups: Vec<BackupFile>,
+    /// Only list the modified backup files and the ones with leftovers.
+    changed_only: bool,
+    rows: Vec<BackupRow>,
+    list_state: ListState,
+    action: LeftoverAction,
+    /// Leftover the diff was computed for.
+    diff_for: Option<String>,
+    /// Backup file and leftover side by side, or why they could not be read.
+    diff: Option<Result<Vec<DiffRow>, String>>,
+    diff_scroll: u16,
+    /// Outcome of the last action.
+    message: Option<String>,
+    running: bool,
+}
+
+impl BackupWidget {
+    pub fn new(backups: Vec<BackupFile>) -> Self {
+        let mut widget = Self {
+            backups,
+            ..Default::default()
+        };
+
+        widget.update_rows();
+        widget
+    }
+
+    /// Show `backups` again after an action, keeping the backup file under the cursor.
+    pub fn set_backups(&mut self, backups: Vec<BackupFile>) {
+        let selected = self.selected().map(|(backup, _)| backup.path.clone());
+
+        self.backups = backups;
+        self.update_rows();
+        self.diff_for = None;
+
+        if let Some(index) = selected.and_then(|path| {
+            self.rows
+                .iter()
+                .position(|row| self.backups[row.backup].path == path)
+        }) {
+            self.list_state.select(Some(index));
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let [msg_area, list_area, diff_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Percentage(35),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.render_msg_box(msg_area, buf, colors);
+        self.render_backups(list_area, buf, colors);
+        self.render_diff(diff_area, buf, colors);
+    }
+
+    pub fn next(&mut self) {
+        self.list_state.select_next();
+        self.message = None;
+    }
+
+    pub fn previous(&mut self) {
+        self.list_state.select_previous();
+        self.message = None;
+    }
+
+    pub fn first(&mut self) {
+        self.list_state.select_first();
+        self.message = None;
+    }
+
+    pub fn last(&mut self) {
+        self.list_state.select_last();
+        self.message = None;
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.diff_scroll = self.diff_scroll.saturating_add(DIFF_SCROLL_STEP);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.diff_scroll = self.diff_scroll.saturating_sub(DIFF_SCROLL_STEP);
+    }
+
+    /// Switch between every backup file and the changed ones only.
+    pub fn toggle_changed_only(&mut self) {
+        self.changed_only = !self.changed_only;
+        self.update_rows();
+    }
+
+    pub fn action(&self) -> LeftoverAction {
+        self.action
+    }
+
+    pub fn set_action(&mut self, action: LeftoverAction) {
+        self.action = action;
+        self.message = None;
+    }
+
+    /// Path of the backup file and of the leftover under the cursor, if any.
+    pub fn selected_leftover(&self) -> Option<(String, String)> {
+        let (backup, leftover) = self.selected()?;
+
+        Some((backup.path.clone(), leftover?.to_string()))
+    }
+
+    /// Whether an action is currently running.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn start(&mut self) {
+        self.running = true;
+        self.message = None;
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    /// Report the outcome of an action.
+    pub fn handle_event(&mut self, event: TransactionEvent) {
+        match event {
+            TransactionEvent::Log(line) => self.message = Some(line),
+            TransactionEvent::Finished(result) => {
+                self.running = false;
+
+                if let Err(err) = result {
+                    self.message = Some(format!("error: {err}"));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Compare the backup file under the cursor with its leftover, unless already done.
+    pub fn load_diff(&mut self, backend: &dyn PackageBackend) {
+        let Some((path, leftover)) = self.selected_leftover() else {
+            self.diff_for = None;
+            self.diff = None;
+            return;
+        };
+
+        if self.diff_for.as_ref() == Some(&leftover) {
+            return;
+        }
+
+        let diff = backend
+            .read_file(&path)
+            .and_then(|old| Ok(diff(&old, &backend.read_file(&leftover)?)))
+            .map_err(|err| err.to_string());
+
+        self.diff = Some(diff);
+        self.diff_for = Some(leftover);
+        self.diff_scroll = 0;
+    }
+
+    fn selected(&self) -> Option<(&BackupFile, Option<&str>)> {
+        // The selection goes past the end until the list is rendered again
+        let index = self
+            .list_state
+            .selected()?
+            .min(self.rows.len().checked_sub(1)?);
+        let row = &self.rows[index];
+        let backup = &self.backups[row.backup];
+
+        Some((
+            backup,
+            row.leftover.map(|index| backup.leftovers[index].as_str()),
+        ))
+    }
+
+    fn update_rows(&mut self) {
+        self.rows = self
+            .backups
+            .iter()
+            .enumerate()
+            .filter(|(_, backup)| {
+                !self.changed_only
+                    || backup.status != BackupStatus::Unmodified
+                    || !backup.leftovers.is_empty()
+            })
+            .flat_map(|(index, backup)| {
+                let leftovers: Vec<Option<usize>> = match backup.leftovers.len() {
+                    0 => vec![None],
+                    len => (0..len).map(Some).collect(),
+                };
+
+                leftovers.into_iter().map(move |leftover| BackupRow {
+                    backup: index,
+                    leftover,
+                })
+            })
+            .collect();
+
+        self.list_state.select_first();
+    }
+
+    fn render_msg_box(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let modified = self
+            .backups
+            .iter()
+            .filter(|backup| backup.status == BackupStatus::Modified)
+            .count();
+        let leftovers: usize = self
+            .backups
+            .iter()
+            .map(|backup| backup.leftovers.len())
+            .sum();
+
+        let selected = self.selected();
+        let legend = selected
+            .filter(|(_, leftover)| leftover.is_some() && !self.running)
+            .map(|_| " ⇄ action (tab) ".to_string());
+
+        let block = create_block(
+            Some(format!(
+                " backups: {modified} modified, {leftovers} .pacnew/.pacsave "
+            )),
+            legend,
+            colors,
+        );
+
+        let message = match (&self.message, selected) {
+            _ if self.running => "Running".to_string(),
+            (Some(message), _) => message.clone(),
+            (None, Some((backup, Some(leftover)))) => format!(
+                "{}: {}? [Enter]",
+                self.action,
+                self.action.description(&backup.path, leftover)
+            ),
+            (None, Some((backup, None))) => {
+                format!("No .pacnew or .pacsave file next to /{}", backup.path)
+            }
+            (None, None) => "No backup files [ESC]".to_string(),
+        };
+
+        Paragraph::new(message)
+            .block(block)
+            .centered()
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
+
+    fn render_backups(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let legend = if self.changed_only {
+            " ↑↓ (k/j) | all (alt+t) | close [ESC] "
+        } else {
+            " ↑↓ (k/j) | changed only (alt+t) | close [ESC] "
+        };
+
+        let block = create_block(None, Some(legend.to_string()), colors);
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let backup = &self.backups[row.backup];
+                let status = Span::from(format!(" [{}]", backup.status));
+
+                let mut spans = vec![
+                    Span::from(format!("{}  /{}", backup.package, backup.path)),
+                    match backup.status {
+                        BackupStatus::Unmodified => status,
+                        _ => status.fg(Color::from_u32(colors.text.warning)),
+                    },
+                ];
+
+                if let Some(leftover) = row.leftover {
+                    spans.push(
+                        Span::from(format!(" → /{}", backup.leftovers[leftover]))
+                            .fg(Color::from_u32(colors.input.typing)),
+                    );
+                }
+
+                ListItem::from(Line::from(spans))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    fn render_diff(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let legend = Some(" scroll (ctrl+u/ctrl+d) ".to_string());
+
+        let (rows, (path, leftover)) = match (&self.diff, self.selected_leftover()) {
+            (Some(Ok(rows)), Some(paths)) => (rows, paths),
+            (Some(Err(err)), Some(_)) => {
+                Paragraph::new(err.clone())
+                    .block(create_block(None, None, colors))
+                    .bg(Color::from_u32(colors.ui.background))
+                    .fg(Color::from_u32(colors.text.warning))
+                    .render(area, buf);
+                return;
+            }
+            _ => {
+                create_block(None, None, colors).render(area, buf);
+                return;
+            }
+        };
+
+        let [old_area, new_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
+
+        let side = |line: &Option<String>, changed: bool, color: u32| match line {
+            Some(line) if changed => Line::from(line.clone()).fg(Color::from_u32(color)),
+            Some(line) => Line::from(line.clone()),
+            None => Line::default(),
+        };
+
+        let (old, new): (Vec<Line>, Vec<Line>) = rows
+            .iter()
+            .map(|row| {
+                let changed = row.old != row.new;
+
+                (
+                    side(&row.old, changed, colors.text.warning),
+                    side(&row.new, changed, colors.ui.key),
+                )
+            })
+            .unzip();
+
+        for (lines, title, area) in [(old, path, old_area), (new, leftover, new_area)] {
+            Paragraph::new(lines)
+                .block(create_block(
+                    Some(format!(" /{title} ")),
+                    legend.clone(),
+                    colors,
+                ))
+                .scroll((self.diff_scroll, 0))
+                .bg(Color::from_u32(colors.ui.background))
+                .fg(Color::from_u32(colors.text.text))
+                .render(area, buf);
+        }
+    }
+}
+
+/// Lines of `old` and `new` side by side, aligned on their longest common subsequence.
+fn diff(old: &str, new: &str) -> Vec<DiffRow> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let mut rows = Vec::new();
+
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        pair_changes(&mut rows, &old, &new);
+        return rows;
+    }
+
+    // Length of the longest common subsequence of the remaining lines from each position
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            pair_changes(&mut rows, &removed, &added);
+            removed.clear();
+            added.clear();
+
+            rows.push(DiffRow {
+                old: Some(old[i].to_string()),
+                new: Some(new[j].to_string()),
+            });
+            i += 1;
+            j += 1;
+        } else if j == new.len()
+            || (i < old.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            removed.push(old[i]);
+            i += 1;
+        } else {
+            added.push(new[j]);
+            j += 1;
+        }
+    }
+
+    pair_changes(&mut rows, &removed, &added);
+    rows
+}
+
+/// Put the lines `removed` and `added` by the same change side by side.
+fn pair_changes(rows: &mut Vec<DiffRow>, removed: &[&str], added: &[&str]) {
+    for index in 0..removed.len().max(added.len()) {
+        rows.push(DiffRow {
+            old: removed.get(index).map(|line| line.to_string()),
+            new: added.get(index).map(|line| line.to_string()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sides(rows: &[DiffRow]) -> Vec<(Option<&str>, Option<&str>)> {
+        rows.iter()
+            .map(|row| (row.old.as_deref(), row.new.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn identical_files_are_aligned() {
+        let rows = diff("a\nb\n", "a\nb\n");
+
+        assert_eq!(
+            sides(&rows),
+            vec![(Some("a"), Some("a")), (Some("b"), Some("b"))]
+        );
+    }
+
+    #[test]
+    fn changes_are_aligned_on_common_lines() {
+        let rows = diff("a\nold\nb\nc\nremoved\n", "added\na\nnew\nb\nc\n");
+
+        assert_eq!(
+            sides(&rows),
+            vec![
+                (None, Some("added")),
+                (Some("a"), Some("a")),
+                (Some("old"), Some("new")),
+                (Some("b"), Some("b")),
+                (Some("c"), Some("c")),
+                (Some("removed"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_files_are_all_changes() {
+        assert!(diff("", "").is_empty());
+        assert_eq!(
+            sides(&diff("", "a\nb")),
+            vec![(None, Some("a")), (None, Some("b"))]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    backend::{PackageData, PackageFile},
+    config::Colors,
+    utils::{create_block, to_human_bytes},
+};
+use crossterm::event::Event;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
+};
+use std::collections::BTreeMap;
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+/// Row of the file list.
+struct FileRow {
+    depth: usize,
+    /// Full path in the flat view, last component in the tree view.
+    label: String,
+    /// Index of the file, `None` for directories only shown to complete the tree.
+    file: Option<usize>,
+}
+
+/// Files of the selected package, as a flat list or a tree.
+#[derive(Default)]
+pub struct FilesWidget {
+    /// Package the files are loaded for.
+    package: String,
+    installed: bool,
+    /// `None` when no file list is available.
+    files: Option<Vec<PackageFile>>,
+    tree_view: bool,
+    filter: Input,
+    filtering: bool,
+    /// Files matching the filter, as shown.
+    rows: Vec<FileRow>,
+    list_state: ListState,
+}
+
+impl FilesWidget {
+    /// Show the `files` of `pkg`, keeping the view mode.
+    pub fn load(&mut self, pkg: &PackageData, files: Option<Vec<PackageFile>>) {
+        *self = Self {
+            package: pkg.name.clone(),
+            installed: pkg.installed,
+            files,
+            tree_view: self.tree_view,
+            ..Default::default()
+        };
+
+        self.update_rows();
+    }
+
+    /// Whether the files of `pkg` are loaded.
+    pub fn is_for(&self, pkg: &PackageData) -> bool {
+        self.package == pkg.name && self.installed == pkg.installed
+    }
+
+    /// Load the files again on the next render, e.g. after a transaction.
+    pub fn invalidate(&mut self) {
+        self.package.clear();
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors, focused: bool) {
+        let legend = if self.filtering {
+            " filter [Enter] | clear [ESC] "
+        } else if focused {
+            " ↑↓ (k/j) | filter (/) | tree/flat (alt+t) | back [ESC] "
+        } else {
+            " ⇄ (tab / shift+tab) | browse [Enter] "
+        };
+
+        let mut block = create_block(None, Some(legend.to_string()), colors);
+
+        let Some(files) = &self.files else {
+            Paragraph::new("No file list available, the .files databases are synced by pacman -Fy")
+                .block(block)
+                .bg(Color::from_u32(colors.ui.background))
+                .fg(Color::from_u32(colors.text.text))
+                .render(area, buf);
+            return;
+        };
+
+        let count = files.iter().filter(|file| !file.is_dir()).count();
+        let missing = files.iter().filter(|file| self.is_missing(file)).count();
+
+        block = block.title(format!(" {count} files, {missing} missing "));
+
+        if self.filtering || !self.filter.value().is_empty() {
+            block = block.title(
+                Line::from(format!(" /{} ", self.filter.value()))
+                    .right_aligned()
+                    .fg(Color::from_u32(colors.input.typing)),
+            );
+        }
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| ListItem::from(self.row_line(row, files, colors)))
+            .collect();
+
+        let mut list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always);
+
+        if focused {
+            list = list
+                .highlight_symbol(" → ")
+                .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+        }
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    pub fn next(&mut self) {
+        self.list_state.select_next();
+    }
+
+    pub fn previous(&mut self) {
+        self.list_state.select_previous();
+    }
+
+    pub fn first(&mut self) {
+        self.list_state.select_first();
+    }
+
+    pub fn last(&mut self) {
+        self.list_state.select_last();
+    }
+
+    /// Switch between the flat list and the tree.
+    pub fn toggle_view(&mut self) {
+        self.tree_view = !self.tree_view;
+        self.update_rows();
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filtering
+    }
+
+    pub fn start_filtering(&mut self) {
+        self.filtering = true;
+    }
+
+    /// Stop typing, keeping the filter.
+    pub fn stop_filtering(&mut self) {
+        self.filtering = false;
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filtering = false;
+        self.filter.reset();
+        self.update_rows();
+    }
+
+    /// Type in the filter.
+    pub fn handle_input(&mut self, event: &Event) {
+        if self
+            .filter
+            .handle_event(event)
+            .is_some_and(|changed| changed.value)
+        {
+            self.update_rows();
+        }
+    }
+
+    /// Installed file that is not on disk anymore.
+    fn is_missing(&self, file: &PackageFile) -> bool {
+        self.installed && file.disk_size.is_none()
+    }
+
+    fn update_rows(&mut self) {
+        let filter = self.filter.value().to_lowercase();
+        let matching = self
+            .files
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, file)| file.path.to_lowercase().contains(&filter));
+
+        self.rows = if self.tree_view {
+            tree_rows(matching)
+        } else {
+            matching
+                .map(|(index, file)| FileRow {
+                    depth: 0,
+                    label: format!("/{}", file.path),
+                    file: Some(index),
+                })
+                .collect()
+        };
+
+        self.list_state.select_first();
+    }
+
+    fn row_line(&self, row: &FileRow, files: &[PackageFile], colors: &Colors) -> Line<'static> {
+        let mut spans = vec![Span::from(format!(
+            "{}{}",
+            "  ".repeat(row.depth),
+            row.label
+        ))];
+
+        let file = row.file.and_then(|index| files.get(index));
+
+        match file {
+            Some(file) if self.is_missing(file) => {
+                spans.push(Span::from(" [missing]").fg(Color::from_u32(colors.text.warning)))
+            }
+            Some(file) if !file.is_dir() => {
+                // Files of packages not installed only have the size recorded, if any
+                let size = match file.disk_size.filter(|_| self.installed) {
+                    Some(size) => size as f64,
+                    None if file.size > 0 => file.size as f64,
+                    None => return Line::from(spans),
+                };
+
+                spans.push(Span::from(format!(" ({})", to_human_bytes(size))));
+            }
+            _ => (),
+        }
+
+        Line::from(spans)
+    }
+}
+
+/// Rows of the tree holding `files`, with the directories leading to them.
+fn tree_rows<'a>(files: impl Iterator<Item = (usize, &'a PackageFile)>) -> Vec<FileRow> {
+    // Paths sort with the content of a directory right after it
+    let mut entries: BTreeMap<String, Option<usize>> = BTreeMap::new();
+
+    for (index, file) in files {
+        for (end, _) in file.path.match_indices('/') {
+            entries.entry(file.path[..=end].to_string()).or_default();
+        }
+        entries.insert(file.path.clone(), Some(index));
+    }
+
+    entries
+        .into_iter()
+        .map(|(path, file)| {
+            let trimmed = path.trim_end_matches('/');
+            let name = trimmed.rsplit('/').next().unwrap_or(trimmed);
+
+            FileRow {
+                depth: trimmed.matches('/').count(),
+                label: if path.ends_with('/') {
+                    format!("{name}/")
+                } else {
+                    name.to_string()
+                },
+                file,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,128 @@
+use crate::{backend::PackageData, config::Colors, utils::create_block};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    widgets::{HighlightSpacing, List, ListItem, ListState, StatefulWidget},
+};
+use std::{collections::BTreeSet, fmt};
+
+/// Packages shown in the list, on top of the search.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Filter {
+    #[default]
+    All,
+    /// Installed explicitly, like `pacman -Qe`.
+    Explicit,
+    /// Installed as a dependency, like `pacman -Qd`.
+    Dependency,
+    /// Dependencies no installed package requires or optionally needs, like `pacman -Qdt`.
+    Orphans,
+    /// Installed packages missing from the sync databases, like `pacman -Qm`.
+    Foreign,
+    /// Packages with an upgrade available, like `pacman -Qu`.
+    Upgrades,
+    /// Packages of a group, like `pacman -Qg`.
+    Group(String),
+}
+
+impl Filter {
+    pub fn matches(&self, pkg: &PackageData) -> bool {
+        match self {
+            Self::All => true,
+            Self::Explicit => pkg.installed && pkg.explicit,
+            Self::Dependency => pkg.installed && !pkg.explicit,
+            Self::Orphans => pkg.is_orphan(),
+            Self::Foreign => pkg.is_foreign(),
+            Self::Upgrades => pkg.new_version.is_some(),
+            Self::Group(group) => pkg.groups.contains(group),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::Explicit => write!(f, "explicit"),
+            Self::Dependency => write!(f, "dependencies"),
+            Self::Orphans => write!(f, "orphans"),
+            Self::Foreign => write!(f, "foreign"),
+            Self::Upgrades => write!(f, "upgrades"),
+            Self::Group(group) => write!(f, "group {group}"),
+        }
+    }
+}
+
+/// Menu picking the filter of the list.
+#[derive(Default)]
+pub struct FilterWidget {
+    filters: Vec<Filter>,
+    list_state: ListState,
+}
+
+impl FilterWidget {
+    /// Menu with the fixed filters and one per group of `packages`, `active` selected.
+    pub fn new<'a>(packages: impl Iterator<Item = &'a PackageData>, active: &Filter) -> Self {
+        let groups: BTreeSet<&String> = packages.flat_map(|pkg| &pkg.groups).collect();
+
+        let filters: Vec<Filter> = [
+            Filter::All,
+            Filter::Explicit,
+            Filter::Dependency,
+            Filter::Orphans,
+            Filter::Foreign,
+            Filter::Upgrades,
+        ]
+        .into_iter()
+        .chain(groups.into_iter().cloned().map(Filter::Group))
+        .collect();
+
+        let selected = filters.iter().position(|filter| filter == active);
+
+        Self {
+            filters,
+            list_state: ListState::default().with_selected(selected.or(Some(0))),
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(
+            Some(" filter ".to_string()),
+            Some(" ↑↓ (k/j) | apply [Enter] | cancel [ESC] ".to_string()),
+            colors,
+        );
+
+        let items: Vec<ListItem> = self
+            .filters
+            .iter()
+            .map(|filter| ListItem::from(filter.to_string()))
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    pub fn next(&mut self) {
+        self.list_state.select_next();
+    }
+
+    pub fn previous(&mut self) {
+        self.list_state.select_previous();
+    }
+
+    /// Filter under the cursor.
+    pub fn selected(&self) -> Option<Filter> {
+        self.list_state
+            .selected()
+            .and_then(|index| self.filters.get(index))
+            .cloned()
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{
+    backend::{HistoryAction, HistoryEntry, HistoryTransaction},
+    config::Colors,
+    error,
+    utils::create_block,
+};
+use crossterm::event::Event;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
+};
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+/// Rows scrolled at once by a jump.
+const JUMP_ROWS: u16 = 25;
+
+/// Row of the history, a transaction followed by its entries.
+struct HistoryRow {
+    transaction: usize,
+    /// Index of the entry, `None` for the transaction itself.
+    entry: Option<usize>,
+}
+
+/// Terms of the history filter.
+///
+/// Words match the packages whose name contains any of them. `date:` terms match the days
+/// starting with their value, like `date:2025-05`, or within a range like
+/// `date:2025-03..2025-04-15`, where either bound can be left out. Every date term must match.
+#[derive(Default)]
+struct HistoryFilter {
+    packages: Vec<String>,
+    /// Inclusive bounds of the dates, empty when open.
+    dates: Vec<(String, String)>,
+}
+
+impl HistoryFilter {
+    fn parse(value: &str) -> Self {
+        let mut filter = Self::default();
+
+        for word in value.split_whitespace() {
+            match word.strip_prefix("date:") {
+                Some(dates) => {
+                    let (from, to) = dates.split_once("..").unwrap_or((dates, dates));
+                    filter.dates.push((from.to_string(), to.to_string()));
+                }
+                None => filter.packages.push(word.to_lowercase()),
+            }
+        }
+
+        filter
+    }
+
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        let day = entry.date.format("%Y-%m-%d").to_string();
+        let name = entry.package.to_lowercase();
+
+        // Bounds are compared to the day truncated to their precision
+        let truncated = |bound: &str| &day[..bound.len().min(day.len())];
+
+        (self.packages.is_empty() || self.packages.iter().any(|word| name.contains(word)))
+            && self
+                .dates
+                .iter()
+                .all(|(from, to)| truncated(from) >= from.as_str() && truncated(to) <= to.as_str())
+    }
+}
+
+/// Transactions recorded in the pacman log, newest first.
+#[derive(Default)]
+pub struct HistoryWidget {
+    transactions: Vec<HistoryTransaction>,
+    /// Why the log could not be read.
+    error: Option<String>,
+    filter: Input,
+    filtering: bool,
+    /// Entries matching the filter with their transactions, as shown.
+    rows: Vec<HistoryRow>,
+    list_state: ListState,
+    /// Why the last jump failed.
+    message: Option<String>,
+}
+
+impl HistoryWidget {
+    pub fn new(history: error::Result<Vec<HistoryTransaction>>) -> Self {
+        let mut widget = match history {
+            Ok(mut transactions) => {
+                transactions.reverse();
+
+                Self {
+                    transactions,
+                    ..Default::default()
+                }
+            }
+            Err(err) => Self {
+                error: Some(err.to_string()),
+                ..Default::default()
+            },
+        };
+
+        widget.update_rows();
+        widget
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let legend = if self.filtering {
+            " filter [Enter] | clear [ESC] "
+        } else {
+            " ↑↓ (k/j) | filter (/) | jump [Enter] | close [ESC] "
+        };
+
+        let mut block = create_block(None, Some(legend.to_string()), colors);
+
+        if let Some(err) = &self.error {
+            Paragraph::new(err.clone())
+                .block(block.title(" history "))
+                .bg(Color::from_u32(colors.ui.background))
+                .fg(Color::from_u32(colors.text.warning))
+                .render(area, buf);
+            return;
+        }
+
+        let title = match &self.message {
+            Some(message) => format!(" {message} "),
+            None => {
+                let entries = self.rows.iter().filter(|row| row.entry.is_some()).count();
+                let transactions = self.rows.len() - entries;
+
+                format!(" history: {entries} changes in {transactions} transactions ")
+            }
+        };
+
+        block = block.title(title);
+
+        if self.filtering || !self.filter.value().is_empty() {
+            block = block.title(
+                Line::from(format!(" /{} ", self.filter.value()))
+                    .right_aligned()
+                    .fg(Color::from_u32(colors.input.typing)),
+            );
+        }
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| ListItem::from(self.row_line(row, colors)))
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    pub fn next(&mut self) {
+        self.list_state.select_next();
+        self.message = None;
+    }
+
+    pub fn previous(&mut self) {
+        self.list_state.select_previous();
+        self.message = None;
+    }
+
+    pub fn first(&mut self) {
+        self.list_state.select_first();
+        self.message = None;
+    }
+
+    pub fn last(&mut self) {
+        self.list_state.select_last();
+        self.message = None;
+    }
+
+    pub fn jump_up(&mut self) {
+        self.list_state.scroll_up_by(JUMP_ROWS);
+        self.message = None;
+    }
+
+    pub fn jump_down(&mut self) {
+        self.list_state.scroll_down_by(JUMP_ROWS);
+        self.message = None;
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filtering
+    }
+
+    pub fn start_filtering(&mut self) {
+        self.filtering = true;
+    }
+
+    /// Stop typing, keeping the filter.
+    pub fn stop_filtering(&mut self) {
+        self.filtering = false;
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filtering = false;
+        self.filter.reset();
+        self.update_rows();
+    }
+
+    /// Type in the filter.
+    pub fn handle_input(&mut self, event: &Event) {
+        if self
+            .filter
+            .handle_event(event)
+            .is_some_and(|changed| changed.value)
+        {
+            self.update_rows();
+        }
+    }
+
+    /// Package of the entry under the cursor.
+    pub fn selected_package(&self) -> Option<&str> {
+        let row = self.rows.get(self.list_state.selected()?)?;
+        let entry = self.transactions[row.transaction].entries.get(row.entry?)?;
+
+        Some(&entry.package)
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    fn update_rows(&mut self) {
+        let filter = HistoryFilter::parse(self.filter.value());
+
+        self.rows = self
+            .transactions
+            .iter()
+            .enumerate()
+            .flat_map(|(index, transaction)| {
+                let entries: Vec<usize> = transaction
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| filter.matches(entry))
+                    .map(|(entry, _)| entry)
+                    .collect();
+
+                // Transactions without matching entries are left out
+                let header = (!entries.is_empty()).then_some(None);
+
+                header
+                    .into_iter()
+                    .chain(entries.into_iter().map(Some))
+                    .map(move |entry| HistoryRow {
+                        transaction: index,
+                        entry,
+                    })
+            })
+            .collect();
+
+        self.list_state.select_first();
+        self.message = None;
+    }
+
+    fn row_line(&self, row: &HistoryRow, colors: &Colors) -> Line<'static> {
+        let transaction = &self.transactions[row.transaction];
+
+        let Some(entry) = row.entry.and_then(|index| transaction.entries.get(index)) else {
+            return Line::from(vec![
+                Span::from(transaction.date.format("%Y-%m-%d %H:%M ").to_string())
+                    .fg(Color::from_u32(colors.text.title)),
+                Span::from(transaction.command.clone().unwrap_or_default()),
+            ]);
+        };
+
+        let versions = match (&entry.old_version, &entry.new_version) {
+            (Some(old), Some(new)) if old != new => format!("{old} → {new}"),
+            (_, Some(version)) | (Some(version), None) => version.clone(),
+            (None, None) => String::new(),
+        };
+
+        let action = Span::from(format!("  {:<12}", entry.action.to_string()));
+
+        Line::from(vec![
+            match entry.action {
+                HistoryAction::Removed | HistoryAction::Downgraded => {
+                    action.fg(Color::from_u32(colors.text.warning))
+                }
+                _ => action,
+            },
+            Span::from(format!("{} {versions}", entry.package)),
+        ])
+    }
+}
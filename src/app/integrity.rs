@@ -0,0 +1,168 @@
+use crate::{
+    backend::{IntegrityEvent, IntegrityReport},
+    config::Colors,
+    utils::create_block,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{
+        Gauge, HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget, Widget,
+    },
+};
+
+/// Report of an integrity check, listing the packages with files differing from their mtree.
+#[derive(Default)]
+pub struct IntegrityWidget {
+    /// Number of packages to check, once the check started.
+    total: Option<usize>,
+    checked: usize,
+    /// Reports of the packages with issues or that could not be checked.
+    reports: Vec<IntegrityReport>,
+    finished: bool,
+    list_state: ListState,
+}
+
+impl IntegrityWidget {
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let [progress_area, report_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
+        let [packages_area, issues_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(2)]).areas(report_area);
+
+        self.render_progress_bar(progress_area, buf, colors);
+        self.render_packages(packages_area, buf, colors);
+        self.render_issues(issues_area, buf, colors);
+    }
+
+    pub fn next(&mut self) {
+        self.list_state.select_next();
+    }
+
+    pub fn previous(&mut self) {
+        self.list_state.select_previous();
+    }
+
+    pub fn handle_event(&mut self, event: IntegrityEvent) {
+        match event {
+            IntegrityEvent::Started(total) => self.total = Some(total),
+            IntegrityEvent::Checked(report) => {
+                self.checked += 1;
+
+                if report.error.is_some() || !report.issues.is_empty() {
+                    self.reports.push(report);
+
+                    if self.list_state.selected().is_none() {
+                        self.list_state.select_first();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every package was checked.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    fn selected_report(&self) -> Option<&IntegrityReport> {
+        self.list_state
+            .selected()
+            .and_then(|index| self.reports.get(index))
+    }
+
+    fn render_progress_bar(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let issues: usize = self.reports.iter().map(|report| report.issues.len()).sum();
+        let block = create_block(
+            Some(format!(
+                " integrity: {issues} issues in {} packages ",
+                self.reports.len()
+            )),
+            None,
+            colors,
+        );
+
+        let (ratio, label) = match self.total {
+            _ if self.finished => (1.0, format!("{} packages checked", self.checked)),
+            Some(total) if total > 0 => (
+                self.checked as f64 / total as f64,
+                format!("{}/{total}", self.checked),
+            ),
+            _ => (0.0, "starting".to_string()),
+        };
+
+        Gauge::default()
+            .block(block)
+            .gauge_style(
+                Style::new()
+                    .fg(Color::from_u32(colors.ui.key))
+                    .bg(Color::from_u32(colors.ui.background)),
+            )
+            .ratio(ratio)
+            .label(label)
+            .render(area, buf);
+    }
+
+    fn render_packages(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(None, Some(" ↑↓ (k/j) | close [ESC] ".to_string()), colors);
+
+        let items: Vec<ListItem> = self
+            .reports
+            .iter()
+            .map(|report| {
+                let status = match &report.error {
+                    Some(_) => "not checked".to_string(),
+                    None => format!("{} issues", report.issues.len()),
+                };
+
+                ListItem::from(Line::from(vec![
+                    Span::from(report.package.clone()),
+                    Span::from(format!(" {status}")).fg(Color::from_u32(colors.text.warning)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    fn render_issues(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(None, None, colors);
+
+        let lines: Vec<Line> = match self.selected_report() {
+            Some(IntegrityReport {
+                error: Some(err), ..
+            }) => vec![Line::from(err.clone())],
+            Some(report) => report
+                .issues
+                .iter()
+                .map(|(path, issue)| {
+                    Line::from(vec![
+                        Span::from(format!("/{path} ")),
+                        Span::from(issue.to_string()).fg(Color::from_u32(colors.text.warning)),
+                    ])
+                })
+                .collect(),
+            None if self.finished => vec![Line::from(format!(
+                "No issues found in {} packages",
+                self.checked
+            ))],
+            None => Vec::new(),
+        };
+
+        Paragraph::new(lines)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
+}
@@ -1,30 +1,63 @@
 use crate::{
-    config::Colors,
+    backend::{
+        FileOwner, IntegrityEvent, PackageBackend, PackageData, Privileges, RemoveMode,
+        Transaction, TransactionEvent,
+    },
+    config::{self, Colors, ColumnConfig, SortOrder, UserConfig},
     error,
     keyboard::{Events, KeyboardEvent, Move, read_event},
-    pacman::Pacman,
-    utils::create_block,
+    utils::{create_block, to_human_bytes},
 };
-use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use backups::{BackupWidget, LeftoverAction};
+use files::FilesWidget;
+use filter::{Filter, FilterWidget};
+use history::HistoryWidget;
+use integrity::IntegrityWidget;
+use orphans::OrphanWidget;
+use owner::OwnerWidget;
+use query::{Qualifier, Query};
 use ratatui::{
     DefaultTerminal, Frame,
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
-    text::Line,
+    text::{Line, Span},
     widgets::{
-        Clear, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph, StatefulWidget,
-        Tabs, Widget,
+        Block, Clear, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph, Row,
+        StatefulWidget, Table, TableState, Tabs, Widget,
     },
 };
-use std::collections::HashSet;
+use refresh::RefreshWidget;
+use remove::RemoveWidget;
+use search::{Search, SearchHit};
+use snapshot::PackageSnapshot;
+use std::{collections::HashSet, future::pending, process::Command};
 use strum::IntoEnumIterator;
 use sync::SyncWidget;
 use tabs::DependenciesTabs;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tree::{DependencyTree, TreeDirection};
 use tui_input::{Input, backend::crossterm::EventHandler};
+use upgrade::UpgradeWidget;
 
+mod backups;
+mod files;
+mod filter;
+mod history;
+mod integrity;
+mod orphans;
+mod owner;
+mod query;
+mod refresh;
+mod remove;
+mod search;
+mod snapshot;
 mod sync;
+mod table;
 mod tabs;
+mod transaction;
+mod tree;
+mod upgrade;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 enum State {
@@ -32,49 +65,189 @@ enum State {
     Normal,
     Searching,
     Syncing(bool),
+    Removing(bool),
+    Upgrading(bool),
+    Refreshing,
+    Filtering,
+    CleaningOrphans,
+    BrowsingTree,
+    BrowsingFiles,
+    LookingUpOwner,
+    CheckingIntegrity,
+    ManagingBackups,
+    BrowsingHistory,
     Exiting,
 }
 
+/// Package database shown in the list.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum View {
+    #[default]
+    Installed,
+    Repositories,
+}
+
 pub struct App {
     state: State,
-    filter_upgradables: bool,
+    view: View,
+    filter: Filter,
+    /// Show the packages as a table instead of a list of names.
+    table_view: bool,
+    table_columns: Vec<ColumnConfig>,
     colors: Colors,
-    pacman: Pacman,
+    backend: Box<dyn PackageBackend>,
+    snapshot: PackageSnapshot,
+    sort: SortOrder,
+    /// Why the last sort order could not be saved.
+    sort_error: Option<String>,
+    /// Positions in the snapshot in sort order.
+    sorted_packages: Vec<usize>,
+    /// Packages matching the search and filter, best matches first.
+    visible_packages: Vec<SearchHit>,
     list_state: ListState,
     dependencies_tabs: DependenciesTabs,
     sync_widget: SyncWidget,
+    remove_widget: RemoveWidget,
+    upgrade_widget: UpgradeWidget,
+    refresh_widget: RefreshWidget,
+    filter_widget: FilterWidget,
+    orphan_widget: OrphanWidget,
+    dependency_tree: DependencyTree,
+    files_widget: FilesWidget,
+    owner_widget: OwnerWidget,
+    integrity_widget: IntegrityWidget,
+    backup_widget: BackupWidget,
+    history_widget: HistoryWidget,
     input: Input,
-    search_matcher: SkimMatcherV2,
+    search: Search,
+    /// Parsed search input.
+    query: Query,
+    /// Why the search input could not be used, e.g. an invalid regex.
+    search_error: Option<String>,
     selected_packages: HashSet<String>,
+    transaction_events: Option<UnboundedReceiver<TransactionEvent>>,
+    integrity_events: Option<UnboundedReceiver<IntegrityEvent>>,
+    owner_lookup: Option<UnboundedReceiver<Vec<FileOwner>>>,
+    /// Merge of a leftover to run once the terminal is handed over.
+    pending_merge: Option<Command>,
+    /// The terminal is handed to a privilege helper authenticating.
+    terminal_suspended: bool,
 }
 
 impl App {
-    pub fn new(pacman: Pacman, colors: Colors) -> Self {
-        Self {
+    pub fn new(backend: Box<dyn PackageBackend>, colors: Colors, user_config: &UserConfig) -> Self {
+        let mut app = Self {
             state: Default::default(),
-            filter_upgradables: false,
+            view: Default::default(),
+            filter: Filter::All,
+            table_view: user_config.table.enabled,
+            table_columns: user_config.table.columns.clone(),
             colors,
-            pacman,
+            backend,
+            snapshot: Default::default(),
+            sort: user_config
+                .sort
+                .or_else(|| config::saved_sort_order().ok())
+                .unwrap_or_default(),
+            sort_error: None,
+            sorted_packages: Vec::new(),
+            visible_packages: Vec::new(),
             list_state: Default::default(),
             dependencies_tabs: Default::default(),
             sync_widget: Default::default(),
+            remove_widget: Default::default(),
+            upgrade_widget: Default::default(),
+            refresh_widget: Default::default(),
+            filter_widget: Default::default(),
+            orphan_widget: Default::default(),
+            dependency_tree: Default::default(),
+            files_widget: Default::default(),
+            owner_widget: Default::default(),
+            integrity_widget: Default::default(),
+            backup_widget: Default::default(),
+            history_widget: Default::default(),
             input: Default::default(),
-            search_matcher: Default::default(),
+            search: Search::new(user_config.search_tie_breaker),
+            query: Query::All,
+            search_error: None,
             selected_packages: HashSet::new(),
-        }
+            transaction_events: None,
+            integrity_events: None,
+            owner_lookup: None,
+            pending_merge: None,
+            terminal_suspended: false,
+        };
+
+        app.reload_snapshot();
+        app
     }
 
     pub async fn run(mut self, terminal: &mut DefaultTerminal) -> error::Result<()> {
         self.list_state.select_first();
 
         while self.state != State::Exiting {
-            terminal.draw(|frame| self.draw(frame))?;
-            self.handle_keyboard_event(read_event().await);
+            if !self.terminal_suspended {
+                terminal.draw(|frame| self.draw(frame))?;
+            }
+
+            tokio::select! {
+                keyboard_event = read_event(), if !self.terminal_suspended => {
+                    self.handle_keyboard_event(keyboard_event)
+                }
+                transaction_event = next_event(&mut self.transaction_events) => {
+                    // The privilege helper is done authenticating once it sends events
+                    if self.terminal_suspended {
+                        *terminal = ratatui::init();
+                        self.terminal_suspended = false;
+                    }
+
+                    match transaction_event {
+                        Some(event) => self.handle_transaction_event(event),
+                        None => self.transaction_events = None,
+                    }
+                }
+                integrity_event = next_event(&mut self.integrity_events) => {
+                    match integrity_event {
+                        Some(event) => self.integrity_widget.handle_event(event),
+                        None => {
+                            self.integrity_events = None;
+                            self.integrity_widget.finish();
+                        }
+                    }
+                }
+                owners = next_event(&mut self.owner_lookup) => {
+                    self.owner_lookup = None;
+
+                    if let Some(owners) = owners {
+                        self.show_owners(owners);
+                    }
+                }
+            }
+
+            if let Some(command) = self.pending_merge.take() {
+                self.run_merge(terminal, command);
+            }
         }
 
         Ok(())
     }
 
+    /// Hand the terminal over to the merge tool until it exits.
+    fn run_merge(&mut self, terminal: &mut DefaultTerminal, mut command: Command) {
+        ratatui::restore();
+        let status = command.status();
+        *terminal = ratatui::init();
+
+        self.backup_widget.set_message(match status {
+            Ok(status) if status.success() => {
+                "Merge done, the leftover can now be deleted".to_string()
+            }
+            Ok(status) => format!("Merge tool exited with {status}"),
+            Err(err) => format!("error: failed to run the merge tool: {err}"),
+        });
+        self.backup_widget.set_backups(self.backend.backup_files());
+    }
+
     pub fn handle_keyboard_event(&mut self, keyboard_event: KeyboardEvent) {
         match self.state {
             State::Normal => {
@@ -82,10 +255,35 @@ impl App {
                     match event {
                         Events::Quit => self.state = State::Exiting,
                         Events::Search => self.state = State::Searching,
-                        Events::Filter => self.filter_upgradables = !self.filter_upgradables,
+                        Events::SearchMode => self.cycle_search_mode(),
+                        Events::Filter => self.open_filter_menu(),
+                        Events::SwitchView => self.switch_view(),
+                        Events::TableView => self.table_view = !self.table_view,
+                        Events::SortKey => self.set_sort_order(SortOrder {
+                            key: self.sort.key.next(),
+                            ..self.sort
+                        }),
+                        Events::SortDirection => self.set_sort_order(SortOrder {
+                            descending: !self.sort.descending,
+                            ..self.sort
+                        }),
                         Events::Select => self.toggle_package_selection(),
                         Events::SelectUpgradables => self.toggle_upgradable_packages(),
                         Events::Sync => self.upgrade_packages(),
+                        Events::Remove => self.remove_packages(),
+                        Events::SystemUpgrade => self.system_upgrade(Vec::new()),
+                        Events::Refresh => self.refresh_databases(),
+                        Events::Orphans => self.clean_orphans(),
+                        Events::DependencyTree => self.open_dependency_tree(),
+                        Events::Owner => self.open_owner_lookup(),
+                        Events::IntegrityCheck => self.check_integrity(),
+                        Events::Backups => self.open_backups(),
+                        Events::History => self.open_history(),
+                        Events::Confirm
+                            if matches!(self.dependencies_tabs, DependenciesTabs::Files) =>
+                        {
+                            self.state = State::BrowsingFiles
+                        }
                         Events::Navigate(mov) => match mov {
                             Move::First => self.list_state.select_first(),
                             Move::Last => self.list_state.select_last(),
@@ -101,6 +299,7 @@ impl App {
                         },
                         Events::Back => {
                             if !self.input.value_and_reset().is_empty() {
+                                self.update_search();
                             } else {
                                 self.state = State::Exiting
                             }
@@ -114,18 +313,20 @@ impl App {
                 if let Some(event) = keyboard_event.event {
                     match event {
                         Events::Confirm => self.state = State::Normal,
+                        Events::SearchMode => self.cycle_search_mode(),
                         Events::Back => {
                             self.input.reset();
+                            self.update_search();
                             self.state = State::Normal;
                         }
-                        _ => _ = self.input.handle_event(&keyboard_event.raw),
+                        _ => self.handle_input_event(&keyboard_event),
                     }
                 } else {
-                    self.input.handle_event(&keyboard_event.raw);
+                    self.handle_input_event(&keyboard_event);
                 }
             }
 
-            State::Syncing(_) => {
+            State::Syncing(started) => {
                 if let Some(event) = keyboard_event.event {
                     match event {
                         Events::Navigate(mov) => match mov {
@@ -133,10 +334,256 @@ impl App {
                             Move::Previous => self.sync_widget.previous(),
                             _ => (),
                         },
+                        Events::Back if !self.sync_widget.is_syncing() => {
+                            self.state = State::Normal
+                        }
+                        Events::Confirm if !started => self.start_sync(),
+                        Events::SystemUpgrade if !started => {
+                            self.system_upgrade(self.selected_packages.iter().cloned().collect())
+                        }
+                        Events::Refresh if !started && self.sync_widget.has_stale_upgrades() => {
+                            self.refresh_system_databases()
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            State::Removing(started) => {
+                if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::Next => self.remove_widget.next(),
+                            Move::Previous => self.remove_widget.previous(),
+                            _ => (),
+                        },
+                        Events::Tab(mov) if !started => match mov {
+                            Move::Next => self.set_remove_mode(self.remove_widget.mode().next()),
+                            Move::Previous => {
+                                self.set_remove_mode(self.remove_widget.mode().previous())
+                            }
+                            _ => (),
+                        },
+                        Events::Back if !self.remove_widget.is_removing() => {
+                            self.state = State::Normal
+                        }
+                        Events::Confirm if self.remove_widget.can_remove() => self.start_removal(),
+                        _ => (),
+                    }
+                }
+            }
+
+            State::Upgrading(started) => {
+                if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::Next => self.upgrade_widget.next(),
+                            Move::Previous => self.upgrade_widget.previous(),
+                            _ => (),
+                        },
+                        Events::Back if !self.upgrade_widget.is_upgrading() => {
+                            self.state = State::Normal
+                        }
+                        Events::Confirm if self.upgrade_widget.can_upgrade() => {
+                            self.start_system_upgrade()
+                        }
+                        Events::Refresh if !started && self.upgrade_widget.has_stale_upgrades() => {
+                            self.refresh_system_databases()
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            State::CleaningOrphans => {
+                if let Some(event) = keyboard_event.event {
+                    let confirming = self.orphan_widget.is_confirming();
+
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::Next => self.orphan_widget.next(),
+                            Move::Previous => self.orphan_widget.previous(),
+                            _ => (),
+                        },
+                        Events::Select if confirming => self.orphan_widget.toggle_keep(),
+                        Events::MarkExplicit if confirming => self.orphan_widget.toggle_explicit(),
+                        Events::Back if !self.orphan_widget.is_cleaning() => {
+                            self.state = State::Normal
+                        }
+                        Events::Confirm if confirming => self.start_orphan_cleanup(),
+                        _ => (),
+                    }
+                }
+            }
+
+            State::Filtering => {
+                if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::Next => self.filter_widget.next(),
+                            Move::Previous => self.filter_widget.previous(),
+                            _ => (),
+                        },
+                        Events::Confirm => self.apply_filter(),
                         Events::Back => self.state = State::Normal,
+                        _ => (),
+                    }
+                }
+            }
+
+            State::BrowsingTree => {
+                if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::First => self.dependency_tree.first(),
+                            Move::Last => self.dependency_tree.last(),
+                            Move::Next => self.dependency_tree.next(),
+                            Move::Previous => self.dependency_tree.previous(),
+                            _ => (),
+                        },
+                        Events::Confirm => self.dependency_tree.toggle(self.backend.as_ref()),
+                        Events::Tab(_) => {
+                            self.dependency_tree.switch_direction(self.backend.as_ref())
+                        }
+                        Events::Back => self.state = State::Normal,
+                        _ => (),
+                    }
+                }
+            }
+
+            State::BrowsingFiles => {
+                if self.files_widget.is_filtering() {
+                    match keyboard_event.event {
+                        Some(Events::Confirm) => self.files_widget.stop_filtering(),
+                        Some(Events::Back) => self.files_widget.clear_filter(),
+                        _ => self.files_widget.handle_input(&keyboard_event.raw),
+                    }
+                } else if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::First => self.files_widget.first(),
+                            Move::Last => self.files_widget.last(),
+                            Move::Next => self.files_widget.next(),
+                            Move::Previous => self.files_widget.previous(),
+                            _ => (),
+                        },
+                        Events::Search => self.files_widget.start_filtering(),
+                        Events::TableView => self.files_widget.toggle_view(),
+                        Events::Back => self.state = State::Normal,
+                        _ => (),
+                    }
+                }
+            }
+
+            State::LookingUpOwner => {
+                if self.owner_widget.is_typing() {
+                    match keyboard_event.event {
+                        Some(Events::Confirm) => self.look_up_owners(),
+                        Some(Events::Back) => self.close_owner_lookup(),
+                        _ => self.owner_widget.handle_input(&keyboard_event.raw),
+                    }
+                } else if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::Next => self.owner_widget.next(),
+                            Move::Previous => self.owner_widget.previous(),
+                            _ => (),
+                        },
+                        Events::Search => self.owner_widget.start_typing(),
                         Events::Confirm => {
-                            self.state = State::Syncing(true);
-                            self.sync_widget.start_sync();
+                            if let Some(owner) = self.owner_widget.selected().cloned() {
+                                self.jump_to_owner(&owner);
+                            }
+                        }
+                        Events::Back => self.close_owner_lookup(),
+                        _ => (),
+                    }
+                }
+            }
+
+            State::CheckingIntegrity => {
+                if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::Next => self.integrity_widget.next(),
+                            Move::Previous => self.integrity_widget.previous(),
+                            _ => (),
+                        },
+                        // Dropping the events stops a running check
+                        Events::Back => {
+                            self.integrity_events = None;
+                            self.state = State::Normal;
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            State::ManagingBackups => {
+                if let Some(event) = keyboard_event.event {
+                    let running = self.backup_widget.is_running();
+
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::First => self.backup_widget.first(),
+                            Move::Last => self.backup_widget.last(),
+                            Move::Next => self.backup_widget.next(),
+                            Move::Previous => self.backup_widget.previous(),
+                            Move::JumpUp => self.backup_widget.scroll_up(),
+                            Move::JumpDown => self.backup_widget.scroll_down(),
+                        },
+                        Events::Tab(mov) if !running => match mov {
+                            Move::Next => self
+                                .backup_widget
+                                .set_action(self.backup_widget.action().next()),
+                            Move::Previous => self
+                                .backup_widget
+                                .set_action(self.backup_widget.action().previous()),
+                            _ => (),
+                        },
+                        Events::TableView => self.backup_widget.toggle_changed_only(),
+                        Events::Confirm if !running => self.resolve_leftover(),
+                        Events::Back if !running => self.state = State::Normal,
+                        _ => (),
+                    }
+                }
+            }
+
+            State::BrowsingHistory => {
+                if self.history_widget.is_filtering() {
+                    match keyboard_event.event {
+                        Some(Events::Confirm) => self.history_widget.stop_filtering(),
+                        Some(Events::Back) => self.history_widget.clear_filter(),
+                        _ => self.history_widget.handle_input(&keyboard_event.raw),
+                    }
+                } else if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::First => self.history_widget.first(),
+                            Move::Last => self.history_widget.last(),
+                            Move::Next => self.history_widget.next(),
+                            Move::Previous => self.history_widget.previous(),
+                            Move::JumpUp => self.history_widget.jump_up(),
+                            Move::JumpDown => self.history_widget.jump_down(),
+                        },
+                        Events::Search => self.history_widget.start_filtering(),
+                        Events::Confirm => self.jump_to_history_package(),
+                        Events::Back => self.state = State::Normal,
+                        _ => (),
+                    }
+                }
+            }
+
+            State::Refreshing => {
+                if let Some(event) = keyboard_event.event {
+                    match event {
+                        Events::Navigate(mov) => match mov {
+                            Move::Next => self.refresh_widget.next(),
+                            Move::Previous => self.refresh_widget.previous(),
+                            _ => (),
+                        },
+                        Events::Back if !self.refresh_widget.is_refreshing() => {
+                            self.state = State::Normal
                         }
                         _ => (),
                     }
@@ -155,8 +602,11 @@ impl App {
                 .margin(1)
                 .areas(area);
 
+        // The table needs more room than the names alone
+        let list_width = if self.table_view { 3 } else { 1 };
         let [left_area, info_area] =
-            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(2)]).areas(main_area);
+            Layout::horizontal([Constraint::Fill(list_width), Constraint::Fill(2)])
+                .areas(main_area);
 
         let [list_area, input_area] =
             Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(left_area);
@@ -171,6 +621,7 @@ impl App {
         self.render_list(list_area, frame.buffer_mut());
         self.render_input(input_area, frame.buffer_mut());
         self.render_general_info(general_info_area, frame.buffer_mut());
+        self.load_files();
         self.render_tabs(tabs_header_area, tabs_inner_area, frame.buffer_mut());
 
         if let State::Syncing(_) = self.state {
@@ -180,6 +631,234 @@ impl App {
             self.sync_widget
                 .render(popup_area, frame.buffer_mut(), &self.colors, vals);
         }
+
+        if let State::Removing(_) = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.remove_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::Upgrading(_) = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.upgrade_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::Refreshing = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.refresh_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::CleaningOrphans = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.orphan_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::BrowsingTree = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.dependency_tree
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::LookingUpOwner = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.owner_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::CheckingIntegrity = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.integrity_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::ManagingBackups = self.state {
+            let popup_area = SyncWidget::area(area, 90, 80);
+            frame.render_widget(Clear, popup_area);
+            self.backup_widget.load_diff(self.backend.as_ref());
+            self.backup_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::BrowsingHistory = self.state {
+            let popup_area = SyncWidget::area(area, 70, 60);
+            frame.render_widget(Clear, popup_area);
+            self.history_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+
+        if let State::Filtering = self.state {
+            let popup_area = SyncWidget::area(area, 30, 50);
+            frame.render_widget(Clear, popup_area);
+            self.filter_widget
+                .render(popup_area, frame.buffer_mut(), &self.colors);
+        }
+    }
+
+    /// Open the dependency tree of the selected package.
+    fn open_dependency_tree(&mut self) {
+        if let Some(pkg) = self.selected_package() {
+            self.dependency_tree =
+                DependencyTree::new(self.backend.as_ref(), pkg, TreeDirection::Dependencies);
+            self.state = State::BrowsingTree;
+        }
+    }
+
+    /// Load the files of the selected package when the files tab shows them.
+    fn load_files(&mut self) {
+        if !matches!(self.dependencies_tabs, DependenciesTabs::Files) {
+            return;
+        }
+
+        if let Some(pkg) = self
+            .selected_package()
+            .filter(|pkg| !self.files_widget.is_for(pkg))
+            .cloned()
+        {
+            let files = self.backend.files(&pkg.name);
+            self.files_widget.load(&pkg, files);
+        }
+    }
+
+    fn open_owner_lookup(&mut self) {
+        self.owner_widget = Default::default();
+        self.state = State::LookingUpOwner;
+    }
+
+    /// Dropping the receiver discards a running lookup.
+    fn close_owner_lookup(&mut self) {
+        self.owner_lookup = None;
+        self.state = State::Normal;
+    }
+
+    /// Find the packages owning the typed file in the background.
+    fn look_up_owners(&mut self) {
+        let query = self.owner_widget.query().to_string();
+
+        if query.is_empty() {
+            return;
+        }
+
+        self.owner_widget.start_lookup();
+        self.owner_lookup = Some(self.backend.spawn_owner_lookup(query));
+    }
+
+    /// Show the owners found, jumping straight to a single owner.
+    fn show_owners(&mut self, owners: Vec<FileOwner>) {
+        match owners.as_slice() {
+            [owner] => self.jump_to_owner(owner),
+            _ => self.owner_widget.set_owners(owners),
+        }
+    }
+
+    /// Select the owner of a file in the view listing it.
+    fn jump_to_owner(&mut self, owner: &FileOwner) {
+        let view = match owner.repository {
+            Some(_) => View::Repositories,
+            None => View::Installed,
+        };
+
+        self.jump_to_package(&owner.package, view);
+    }
+
+    /// Select the package of the history entry under the cursor, installed or not.
+    fn jump_to_history_package(&mut self) {
+        let Some(name) = self.history_widget.selected_package().map(String::from) else {
+            return;
+        };
+
+        match self.backend.package(&name) {
+            Some(pkg) if pkg.installed => self.jump_to_package(&name, View::Installed),
+            Some(_) => self.jump_to_package(&name, View::Repositories),
+            None => self.history_widget.set_message(format!(
+                "{name} is neither installed nor in the repositories"
+            )),
+        }
+    }
+
+    /// Select the package named `name` in `view`, clearing the search and filter hiding it.
+    fn jump_to_package(&mut self, name: &str, view: View) {
+        if self.view != view {
+            self.view = view;
+            self.reload_snapshot();
+        }
+
+        if !self.select_package(name) {
+            self.input.reset();
+            self.filter = Filter::All;
+            self.update_search();
+            self.select_package(name);
+        }
+
+        self.state = State::Normal;
+    }
+
+    /// Check the files of the selected packages, or of every installed package.
+    fn check_integrity(&mut self) {
+        let packages = self.selected_packages.iter().cloned().collect();
+
+        self.integrity_widget = Default::default();
+        self.integrity_events = Some(self.backend.spawn_integrity_check(packages));
+        self.state = State::CheckingIntegrity;
+    }
+
+    /// Open the backup files of the installed packages with their leftovers.
+    fn open_backups(&mut self) {
+        self.backup_widget = BackupWidget::new(self.backend.backup_files());
+        self.state = State::ManagingBackups;
+    }
+
+    /// Merge, replace or delete the leftover under the cursor.
+    fn resolve_leftover(&mut self) {
+        let Some((path, leftover)) = self.backup_widget.selected_leftover() else {
+            return;
+        };
+
+        let transaction = match self.backup_widget.action() {
+            LeftoverAction::Merge => {
+                match self.backend.merge_command(&path, &leftover) {
+                    Ok(command) => self.pending_merge = Some(command),
+                    Err(err) => self.backup_widget.set_message(format!("error: {err}")),
+                }
+                return;
+            }
+            LeftoverAction::Replace => Transaction::ReplaceBackup(leftover),
+            LeftoverAction::Delete => Transaction::DeleteLeftover(leftover),
+        };
+
+        self.backup_widget.start();
+        self.spawn_transaction(transaction);
+    }
+
+    /// Open the transactions recorded in the pacman log.
+    fn open_history(&mut self) {
+        self.history_widget = HistoryWidget::new(self.backend.history());
+        self.state = State::BrowsingHistory;
+    }
+
+    fn open_filter_menu(&mut self) {
+        self.filter_widget = FilterWidget::new(self.snapshot.packages().iter(), &self.filter);
+        self.state = State::Filtering;
+    }
+
+    /// Show only the packages matching the filter picked in the menu.
+    fn apply_filter(&mut self) {
+        if let Some(filter) = self.filter_widget.selected() {
+            self.filter = filter;
+            self.update_visible_packages();
+            self.list_state.select_first();
+        }
+
+        self.state = State::Normal;
     }
 
     fn jump_up(&mut self) {
@@ -201,33 +880,281 @@ impl App {
     fn upgrade_packages(&mut self) {
         if !self.selected_packages.is_empty() {
             self.state = State::Syncing(false);
+            self.sync_widget = Default::default();
+            self.sync_widget
+                .set_partial_upgrade(self.backend.partial_upgrade(&self.selected_packages));
+            self.sync_widget.set_stale_upgrades(
+                self.backend
+                    .stale_upgrades()
+                    .into_iter()
+                    .filter(|name| self.selected_packages.contains(name))
+                    .collect(),
+            );
         }
-        // self.stdout = self.pacman.upgrade(&self.selected_packages).ok();
     }
 
-    fn toggle_package_selection(&mut self) {
-        if let Some(selected_index) = self.list_state.selected() {
-            let package_name = self
-                .pacman
-                .packages()
-                .filter(|pkg| {
-                    let search = self
-                        .search_matcher
-                        .fuzzy_match(pkg.name, self.input.value())
-                        .is_some();
-                    let filter = if self.filter_upgradables {
-                        pkg.new_version.is_some()
-                    } else {
-                        true
-                    };
-
-                    search && filter
-                })
-                .enumerate()
-                .find(|(index, _)| *index == selected_index)
-                .map(|(_, pkg)| pkg.name.to_string())
-                .unwrap();
+    /// Launch the sync transaction for the selected packages in the background.
+    fn start_sync(&mut self) {
+        self.state = State::Syncing(true);
+        self.sync_widget.start_sync();
+        self.spawn_transaction(Transaction::Sync(
+            self.selected_packages.iter().cloned().collect(),
+        ));
+    }
+
+    fn remove_packages(&mut self) {
+        if !self.selected_packages.is_empty() {
+            self.state = State::Removing(false);
+            self.remove_widget = Default::default();
+            self.set_remove_mode(Default::default());
+        }
+    }
+
+    /// Change the removal mode and preview what it would remove.
+    fn set_remove_mode(&mut self, mode: RemoveMode) {
+        let packages: Vec<String> = self.selected_packages.iter().cloned().collect();
+        let preview = self.backend.removal_preview(&packages, mode);
+
+        self.remove_widget.set_mode(mode);
+        self.remove_widget.set_preview(preview);
+    }
+
+    /// Launch the removal transaction for the selected packages in the background.
+    fn start_removal(&mut self) {
+        self.state = State::Removing(true);
+        self.remove_widget.start_removal();
+        self.spawn_transaction(Transaction::Remove(
+            self.selected_packages.iter().cloned().collect(),
+            self.remove_widget.mode(),
+        ));
+    }
+
+    /// Open the full system upgrade popup, installing `packages` along the upgrade.
+    fn system_upgrade(&mut self, packages: Vec<String>) {
+        let preview = self.backend.system_upgrade_preview(&packages);
+
+        self.state = State::Upgrading(false);
+        self.upgrade_widget = UpgradeWidget::new(packages);
+        self.upgrade_widget.set_preview(preview);
+        self.upgrade_widget
+            .set_stale_upgrades(self.backend.stale_upgrades());
+    }
+
+    /// Launch the full system upgrade in the background.
+    fn start_system_upgrade(&mut self) {
+        self.state = State::Upgrading(true);
+        self.upgrade_widget.start_upgrade();
+        self.spawn_transaction(Transaction::SystemUpgrade(
+            self.upgrade_widget.packages().to_vec(),
+        ));
+    }
 
+    /// Open the orphan cleanup assistant.
+    fn clean_orphans(&mut self) {
+        self.state = State::CleaningOrphans;
+        self.orphan_widget = OrphanWidget::new(self.backend.orphans());
+    }
+
+    /// Launch the orphan cleanup in the background.
+    fn start_orphan_cleanup(&mut self) {
+        if let Some(transaction) = self.orphan_widget.transaction() {
+            self.orphan_widget.start_cleaning();
+            self.spawn_transaction(transaction);
+        }
+    }
+
+    /// Refresh the package databases in the background.
+    pub fn refresh_databases(&mut self) {
+        self.state = State::Refreshing;
+        self.refresh_widget = Default::default();
+        self.spawn_transaction(Transaction::Refresh);
+    }
+
+    /// Refresh the system sync databases the transactions run against in the background.
+    fn refresh_system_databases(&mut self) {
+        self.state = State::Refreshing;
+        self.refresh_widget = Default::default();
+        self.spawn_transaction(Transaction::RefreshSystem);
+    }
+
+    /// Run a transaction in the background, handing the terminal over to the privilege helper
+    /// while it authenticates.
+    fn spawn_transaction(&mut self, transaction: Transaction) {
+        if self.backend.requires_root(&transaction) {
+            let privileges = self.backend.privileges();
+
+            if let Some(reason) = privileges.read_only_reason() {
+                return self.forward_transaction_event(TransactionEvent::Finished(Err(format!(
+                    "read-only mode, {reason}"
+                ))));
+            }
+
+            if let Privileges::Escalate(helper) = privileges {
+                ratatui::restore();
+                println!("Authenticating with {helper} to run the transaction...");
+                self.terminal_suspended = true;
+            }
+        }
+
+        self.transaction_events = Some(self.backend.spawn_transaction(transaction));
+    }
+
+    fn handle_transaction_event(&mut self, event: TransactionEvent) {
+        if let TransactionEvent::Finished(result) = &event {
+            // Syncs, removals and system upgrades consume the selection, refreshes, orphan
+            // cleanups and backup actions keep it
+            if result.is_ok()
+                && !matches!(
+                    self.state,
+                    State::Refreshing | State::CleaningOrphans | State::ManagingBackups
+                )
+            {
+                self.selected_packages.clear();
+            }
+
+            // Even a failed transaction may have changed the local database
+            if let Err(err) = self.backend.reload() {
+                self.forward_transaction_event(TransactionEvent::Log(format!("error: {err}")));
+            }
+            self.reload_snapshot();
+
+            if let State::ManagingBackups = self.state {
+                self.backup_widget.set_backups(self.backend.backup_files());
+            }
+        }
+
+        self.forward_transaction_event(event);
+    }
+
+    /// Send a transaction event to the popup that started the transaction.
+    fn forward_transaction_event(&mut self, event: TransactionEvent) {
+        match self.state {
+            State::Removing(_) => self.remove_widget.handle_event(event),
+            State::Upgrading(_) => self.upgrade_widget.handle_event(event),
+            State::Refreshing => self.refresh_widget.handle_event(event),
+            State::CleaningOrphans => self.orphan_widget.handle_event(event),
+            State::ManagingBackups => self.backup_widget.handle_event(event),
+            _ => self.sync_widget.handle_event(event),
+        }
+    }
+
+    /// Switch between installed packages and the sync repositories.
+    fn switch_view(&mut self) {
+        self.view = match self.view {
+            View::Installed => View::Repositories,
+            View::Repositories => View::Installed,
+        };
+        self.reload_snapshot();
+        self.list_state.select_first();
+    }
+
+    /// Copy the packages of the current view from the backend, keeping the selected package.
+    fn reload_snapshot(&mut self) {
+        let selected_name = self.selected_package().map(|pkg| pkg.name.clone());
+
+        self.snapshot = match self.view {
+            View::Installed => PackageSnapshot::new(self.backend.packages()),
+            View::Repositories => PackageSnapshot::new(self.backend.repository_packages()),
+        };
+        self.sorted_packages = self.snapshot.sorted(self.sort);
+        self.update_visible_packages();
+        self.files_widget.invalidate();
+
+        if let Some(name) = selected_name {
+            self.select_package(&name);
+        }
+    }
+
+    /// Sort the list, keeping the selected package, and save the order.
+    fn set_sort_order(&mut self, sort: SortOrder) {
+        let selected_name = self.selected_package().map(|pkg| pkg.name.clone());
+
+        self.sort = sort;
+        self.sorted_packages = self.snapshot.sorted(sort);
+        self.update_visible_packages();
+
+        if let Some(name) = selected_name {
+            self.select_package(&name);
+        }
+
+        // The new order still applies to this session when it cannot be saved
+        self.sort_error = config::save_sort_order(sort)
+            .err()
+            .map(|err| err.to_string());
+    }
+
+    /// Move the cursor to the package named `name`, returning whether it is visible.
+    fn select_package(&mut self, name: &str) -> bool {
+        if let Some(position) = self.snapshot.position(name)
+            && let Some(index) = self
+                .visible_packages
+                .iter()
+                .position(|hit| hit.position == position)
+        {
+            self.list_state.select(Some(index));
+            return true;
+        }
+
+        false
+    }
+
+    /// Recompute the packages matching the search and filter.
+    fn update_visible_packages(&mut self) {
+        let packages = self
+            .sorted_packages
+            .iter()
+            .filter_map(|position| Some((*position, self.snapshot.get(*position)?)))
+            .filter(|(_, pkg)| self.filter.matches(pkg));
+
+        self.visible_packages = self.search.hits(packages, &self.query);
+    }
+
+    /// Recompute the packages matching a new query and select the best match.
+    ///
+    /// An invalid query is reported and the previous results are kept.
+    fn update_search(&mut self) {
+        let query = Query::parse(self.input.value());
+
+        match self.search.prepare(&query) {
+            Ok(()) => {
+                self.query = query;
+                self.search_error = None;
+                self.update_visible_packages();
+                self.list_state.select_first();
+            }
+            Err(err) => {
+                // Regex errors span several lines, the last one describes the error
+                let err = err.to_string();
+                self.search_error = err.lines().last().map(|line| line.to_string());
+            }
+        }
+    }
+
+    fn cycle_search_mode(&mut self) {
+        self.search.set_mode(self.search.mode().next());
+        self.update_search();
+    }
+
+    fn handle_input_event(&mut self, keyboard_event: &KeyboardEvent) {
+        if self
+            .input
+            .handle_event(&keyboard_event.raw)
+            .is_some_and(|changed| changed.value)
+        {
+            self.update_search();
+        }
+    }
+
+    /// Package under the cursor.
+    fn selected_package(&self) -> Option<&PackageData> {
+        self.list_state
+            .selected()
+            .and_then(|index| self.visible_packages.get(index))
+            .and_then(|hit| self.snapshot.get(hit.position))
+    }
+
+    fn toggle_package_selection(&mut self) {
+        if let Some(package_name) = self.selected_package().map(|pkg| pkg.name.clone()) {
             if self.selected_packages.contains(&package_name) {
                 self.selected_packages.remove(&package_name);
             } else {
@@ -237,9 +1164,9 @@ impl App {
     }
 
     fn toggle_upgradable_packages(&mut self) {
-        let package_names = self.pacman.packages().filter_map(|pkg| {
+        let package_names = self.snapshot.packages().iter().filter_map(|pkg| {
             if pkg.new_version.is_some() {
-                Some(pkg.name.to_string())
+                Some(pkg.name.clone())
             } else {
                 None
             }
@@ -257,7 +1184,17 @@ impl App {
     }
 
     fn render_header(&self, area: Rect, buf: &mut Buffer) {
-        let block = create_block(None, None, &self.colors);
+        let privileges = self.backend.privileges();
+        let mut block = create_block(None, None, &self.colors);
+
+        if let Some(reason) = privileges.read_only_reason() {
+            block = block.title_bottom(
+                Line::from(format!(" read-only: {reason} "))
+                    .fg(Color::from_u32(self.colors.text.warning)),
+            );
+        } else if let Privileges::Escalate(helper) = privileges {
+            block = block.title_bottom(format!(" transactions through {helper} "));
+        }
 
         Paragraph::new("Tecarius - Pacman Librarian 󱉟 ")
             .block(block)
@@ -270,71 +1207,77 @@ impl App {
     }
 
     fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
-        let total_packages = self.pacman.packages().count();
-
-        let packages_names: Vec<ListItem> = match self.filter_upgradables {
-            true => self
-                .pacman
-                .packages()
-                .filter_map(|pkg| {
-                    if self
-                        .search_matcher
-                        .fuzzy_match(pkg.name, self.input.value())
-                        .is_some()
-                        && pkg.new_version.is_some()
-                    {
-                        if self.selected_packages.contains(pkg.name) {
-                            Some(ListItem::from(format!("  {}  ", pkg.name)))
-                        } else if !self.selected_packages.is_empty() {
-                            Some(ListItem::from(format!("  {}  ", pkg.name)))
-                        } else {
-                            Some(ListItem::from(format!("{}  ", pkg.name)))
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect(),
-            false => self
-                .pacman
-                .packages()
-                .filter_map(|pkg| {
-                    self.search_matcher
-                        .fuzzy_match(pkg.name, self.input.value())
-                        .map(|_| {
-                            match (
-                                pkg.new_version.is_some(),
-                                self.selected_packages.contains(pkg.name),
-                                self.selected_packages.is_empty(),
-                            ) {
-                                (true, true, _) => ListItem::from(format!("  {}  ", pkg.name)),
-                                (true, false, true) => ListItem::from(format!("{}  ", pkg.name)),
-                                (true, false, false) => {
-                                    ListItem::from(format!("  {}  ", pkg.name))
-                                }
-                                (false, true, _) => ListItem::from(format!("  {}", pkg.name)),
-                                (false, false, false) => ListItem::from(format!("  {}", pkg.name)),
-                                (false, false, true) => ListItem::from(pkg.name),
-                            }
-                        })
-                })
-                .collect(),
-        };
+        let total_packages = self.snapshot.len();
+
+        let title = match self.view {
+            View::Installed => {
+                let upgradable_count = self
+                    .snapshot
+                    .packages()
+                    .iter()
+                    .filter(|pkg| pkg.new_version.is_some())
+                    .count();
 
-        let upgradable_count = self
-            .pacman
-            .packages()
-            .filter(|pkg| pkg.new_version.is_some())
-            .count();
+                format!(
+                    " packages   ({} 󰏖  {}  ) ",
+                    total_packages, upgradable_count
+                )
+            }
+            View::Repositories => {
+                let installed_count = self
+                    .snapshot
+                    .packages()
+                    .iter()
+                    .filter(|pkg| pkg.installed)
+                    .count();
 
+                format!(
+                    " repositories   ({} 󰏖  {}  ) ",
+                    total_packages, installed_count
+                )
+            }
+        };
+
+        let direction = if self.sort.descending { "↓" } else { "↑" };
         let block = create_block(
-            Some(format!(
-                " packages   ({} 󰏖  {}  ) ",
-                total_packages, upgradable_count
-            )),
-            Some("↑↓ (k/j) (g/G) (c-d/c-u) | filter (alt+u)".to_string()),
+            Some(title),
+            Some(
+                "↑↓ (k/j) (g/G) (c-d/c-u) | filter (alt+u) | view (alt+r) | table (alt+t)"
+                    .to_string(),
+            ),
             &self.colors,
+        )
+        .title_bottom(
+            Line::from(format!(" {} {direction} (alt+s/d) ", self.sort.key)).right_aligned(),
         );
+        let block = match &self.sort_error {
+            Some(err) => block.title_bottom(
+                Line::from(format!(" {err} "))
+                    .right_aligned()
+                    .fg(Color::from_u32(self.colors.text.warning)),
+            ),
+            None => block,
+        };
+        let block = if self.filter != Filter::All {
+            block.title(Line::from(format!(" {} ", self.filter)).right_aligned())
+        } else {
+            block
+        };
+
+        if self.table_view {
+            self.render_table(block, area, buf);
+            return;
+        }
+
+        let packages_names: Vec<ListItem> = self
+            .visible_packages
+            .iter()
+            .filter_map(|hit| {
+                Some(ListItem::from(
+                    self.name_line(self.snapshot.get(hit.position)?, hit),
+                ))
+            })
+            .collect();
 
         let name_list = List::new(packages_names)
             .block(block)
@@ -347,9 +1290,102 @@ impl App {
         StatefulWidget::render(name_list, area, buf, &mut self.list_state);
     }
 
+    fn render_table(&mut self, block: Block, area: Rect, buf: &mut Buffer) {
+        let rows: Vec<Row> = self
+            .visible_packages
+            .iter()
+            .filter_map(|hit| {
+                let pkg = self.snapshot.get(hit.position)?;
+                Some(table::row(
+                    &self.table_columns,
+                    pkg,
+                    self.name_line(pkg, hit),
+                ))
+            })
+            .collect();
+
+        let header = table::header(&self.table_columns, self.sort)
+            .fg(Color::from_u32(self.colors.text.title))
+            .bold();
+
+        let package_table = Table::new(rows, table::widths(&self.table_columns))
+            .header(header)
+            .block(block)
+            .bg(Color::from_u32(self.colors.ui.background))
+            .fg(Color::from_u32(self.colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .row_highlight_style(Style::new().fg(Color::from_u32(self.colors.ui.key)));
+
+        // The table shares the list state so navigation works the same in both views
+        let selected = self
+            .list_state
+            .selected()
+            .map(|index| index.min(self.visible_packages.len().saturating_sub(1)));
+        let mut table_state = TableState::default()
+            .with_offset(self.list_state.offset())
+            .with_selected(selected);
+
+        StatefulWidget::render(package_table, area, buf, &mut table_state);
+
+        self.list_state.select(table_state.selected());
+        *self.list_state.offset_mut() = table_state.offset();
+    }
+
+    /// Package name with its selection, upgrade and install markers and the characters
+    /// matching the search highlighted.
+    fn name_line(&self, pkg: &PackageData, hit: &SearchHit) -> Line<'static> {
+        let mut spans = Vec::new();
+
+        if self.selected_packages.contains(&pkg.name) {
+            spans.push(Span::from("  "));
+        } else if !self.selected_packages.is_empty() {
+            spans.push(Span::from("  "));
+        }
+
+        let highlight = Style::new().fg(Color::from_u32(self.colors.ui.key)).bold();
+        spans.extend(pkg.name.chars().enumerate().map(|(index, char)| {
+            if hit.indices.contains(&index) {
+                Span::styled(char.to_string(), highlight)
+            } else {
+                Span::from(char.to_string())
+            }
+        }));
+
+        if pkg.new_version.is_some() {
+            spans.push(Span::from("  "));
+        }
+
+        if self.view == View::Repositories && pkg.installed {
+            spans.push(Span::from("  "));
+        }
+
+        Line::from(spans)
+    }
+
     fn render_input(&self, area: Rect, buf: &mut Buffer) {
-        let block = create_block(None, Some(" search (/) ".to_string()), &self.colors)
-            .padding(Padding::horizontal(3));
+        let qualifiers = self.query.qualifiers();
+        let legend = if !qualifiers.is_empty() {
+            let qualifiers: Vec<String> = qualifiers.iter().map(Qualifier::to_string).collect();
+            format!(" search (/) | {} ", qualifiers.join(" "))
+        } else if self.state == State::Searching {
+            let qualifiers: Vec<String> = Qualifier::iter()
+                .map(|qualifier| qualifier.to_string())
+                .collect();
+            format!(" {} | or (|) | not (!) | group ( ) ", qualifiers.join(" "))
+        } else {
+            " search (/) ".to_string()
+        };
+        let mode = format!(" {} (alt+m) ", self.search.mode());
+        let mut block =
+            create_block(Some(mode), Some(legend), &self.colors).padding(Padding::horizontal(3));
+        if let Some(err) = &self.search_error {
+            block = block.title(
+                Line::from(format!(" {err} "))
+                    .right_aligned()
+                    .fg(Color::from_u32(self.colors.text.warning)),
+            );
+        }
         let width = area.width.max(3) - 3;
         let scroll = self.input.visual_scroll(width as usize);
         let block = match self.state {
@@ -371,47 +1407,40 @@ impl App {
     fn render_general_info(&mut self, area: Rect, buf: &mut Buffer) {
         let block = create_block(Some(" package info  ".to_string()), None, &self.colors);
 
-        if let Some(selected_index) = self.list_state.selected() {
-            let package = self
-                .pacman
-                .packages()
-                .filter(|pkg| {
-                    let search = self
-                        .search_matcher
-                        .fuzzy_match(pkg.name, self.input.value())
-                        .is_some();
-                    let filter = if self.filter_upgradables {
-                        pkg.new_version.is_some()
-                    } else {
-                        true
-                    };
-
-                    search && filter
-                })
-                .enumerate()
-                .find(|(index, _)| *index == selected_index)
-                .unwrap()
-                .1;
+        if let Some(package) = self.selected_package() {
             let color = Color::from_u32(self.colors.ui.key);
             let mut lines: Vec<Line> = Vec::new();
 
-            lines.push(Line::from(vec!["Name: ".fg(color), package.name.into()]));
+            lines.push(Line::from(vec![
+                "Name: ".fg(color),
+                package.name.as_str().into(),
+            ]));
             lines.push(Line::from(vec![
                 "Version: ".fg(color),
-                package.version.to_string().into(),
+                package.version.as_str().into(),
             ]));
-            if let Some(desc) = package.description {
+            if let Some(desc) = &package.description {
                 lines.push(Line::from(vec!["Description: ".fg(color), desc.into()]));
             }
-            if let Some(arch) = package.architecture {
+            if let Some(arch) = &package.architecture {
                 lines.push(Line::from(vec!["Architecture: ".fg(color), arch.into()]));
             }
-            if let Some(url) = package.url {
+            if let Some(url) = &package.url {
                 lines.push(Line::from(vec!["Url: ".fg(color), url.into()]));
             }
+            if let Some(repository) = &package.repository {
+                lines.push(Line::from(vec![
+                    "Repository: ".fg(color),
+                    repository.into(),
+                ]));
+            }
+            if self.view == View::Repositories {
+                let installed = if package.installed { "yes" } else { "no" };
+                lines.push(Line::from(vec!["Installed: ".fg(color), installed.into()]));
+            }
             lines.push(Line::from(vec![
                 "Size: ".fg(color),
-                package.size.to_string().into(),
+                to_human_bytes(package.size as f64).into(),
             ]));
 
             if let Some(updated_at) = package.install_date {
@@ -421,12 +1450,12 @@ impl App {
                 ]));
             }
 
-            if let Some(new_version) = package.new_version {
+            if let Some(new_version) = &package.new_version {
                 lines.push(Line::from(vec![
                     "New version available: ".fg(color),
-                    package.version.to_string().into(),
+                    package.version.as_str().into(),
                     " → ".into(),
-                    new_version.to_string().into(),
+                    new_version.as_str().into(),
                 ]));
             }
 
@@ -440,7 +1469,7 @@ impl App {
         }
     }
 
-    fn render_tabs(&self, header_area: Rect, inner_area: Rect, buf: &mut Buffer) {
+    fn render_tabs(&mut self, header_area: Rect, inner_area: Rect, buf: &mut Buffer) {
         let titles = DependenciesTabs::iter().map(DependenciesTabs::title);
 
         Tabs::new(titles)
@@ -452,17 +1481,23 @@ impl App {
             .divider("")
             .render(header_area, buf);
 
-        if let Some(selected_index) = self.list_state.selected() {
-            let package = self
-                .pacman
-                .packages()
-                .enumerate()
-                .find(|(index, _)| *index == selected_index)
-                .unwrap()
-                .1;
-
+        if let DependenciesTabs::Files = self.dependencies_tabs {
+            if self.selected_package().is_some() {
+                let focused = self.state == State::BrowsingFiles;
+                self.files_widget
+                    .render(inner_area, buf, &self.colors, focused);
+            }
+        } else if let Some(package) = self.selected_package() {
             self.dependencies_tabs
-                .render(inner_area, buf, &package, &self.colors);
+                .render(inner_area, buf, package, &self.colors);
         }
     }
 }
+
+/// Wait for the next event of a running background task, if any.
+async fn next_event<T>(events: &mut Option<UnboundedReceiver<T>>) -> Option<T> {
+    match events {
+        Some(events) => events.recv().await,
+        None => pending().await,
+    }
+}
@@ -0,0 +1,238 @@
+use super::transaction::TransactionLog;
+use crate::{
+    backend::{PackageData, Transaction, TransactionEvent, recursive_orphans},
+    config::Colors,
+    utils::{create_block, to_human_bytes},
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
+};
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Default)]
+pub enum OrphanStates {
+    #[default]
+    Confirmation,
+    Cleaning,
+    Finished(bool),
+}
+
+/// Assistant removing orphaned dependencies, keeping the ones picked by the user.
+#[derive(Default)]
+pub struct OrphanWidget {
+    state: OrphanStates,
+    orphans: Vec<PackageData>,
+    /// Orphans kept installed as dependencies.
+    keep: HashSet<String>,
+    /// Orphans kept and marked as explicitly installed.
+    mark_explicit: HashSet<String>,
+    /// Orphans removed, without the kept ones and what they need.
+    remove: HashSet<String>,
+    list_state: ListState,
+    transaction_log: TransactionLog,
+}
+
+impl OrphanWidget {
+    pub fn new(orphans: Vec<PackageData>) -> Self {
+        let remove = orphans.iter().map(|pkg| pkg.name.clone()).collect();
+
+        Self {
+            orphans,
+            remove,
+            list_state: ListState::default().with_selected(Some(0)),
+            ..Default::default()
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let [msg_area, progress_area, list_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.render_msg_box(msg_area, buf, colors);
+        self.transaction_log
+            .render_progress_bar(progress_area, buf, colors);
+
+        match self.state {
+            OrphanStates::Confirmation => self.render_orphans(list_area, buf, colors),
+            _ => self.transaction_log.render_log(list_area, buf, colors),
+        }
+    }
+
+    pub fn next(&mut self) {
+        match self.state {
+            OrphanStates::Confirmation => self.list_state.select_next(),
+            _ => self.transaction_log.next(),
+        }
+    }
+
+    pub fn previous(&mut self) {
+        match self.state {
+            OrphanStates::Confirmation => self.list_state.select_previous(),
+            _ => self.transaction_log.previous(),
+        }
+    }
+
+    /// Keep or remove the orphan under the cursor.
+    pub fn toggle_keep(&mut self) {
+        if let Some(name) = self.selected_name() {
+            if !self.keep.remove(&name) && !self.mark_explicit.remove(&name) {
+                self.keep.insert(name);
+            }
+            self.update_removals();
+        }
+    }
+
+    /// Keep the orphan under the cursor and mark it as explicitly installed, or undo it.
+    pub fn toggle_explicit(&mut self) {
+        if let Some(name) = self.selected_name() {
+            self.keep.remove(&name);
+            if !self.mark_explicit.remove(&name) {
+                self.mark_explicit.insert(name);
+            }
+            self.update_removals();
+        }
+    }
+
+    /// Transaction cleaning up the orphans, if there is anything to do.
+    pub fn transaction(&self) -> Option<Transaction> {
+        if self.remove.is_empty() && self.mark_explicit.is_empty() {
+            return None;
+        }
+
+        Some(Transaction::CleanOrphans {
+            remove: self.names(&self.remove),
+            mark_explicit: self.names(&self.mark_explicit),
+        })
+    }
+
+    /// Whether a transaction is currently running.
+    pub fn is_cleaning(&self) -> bool {
+        matches!(self.state, OrphanStates::Cleaning)
+    }
+
+    /// Whether the orphans are still being picked.
+    pub fn is_confirming(&self) -> bool {
+        matches!(self.state, OrphanStates::Confirmation)
+    }
+
+    pub fn start_cleaning(&mut self) {
+        self.state = OrphanStates::Cleaning;
+        self.transaction_log = Default::default();
+    }
+
+    /// Update the log and progress bar with a transaction event.
+    pub fn handle_event(&mut self, event: TransactionEvent) {
+        if let TransactionEvent::Finished(result) = &event {
+            self.state = OrphanStates::Finished(result.is_ok());
+        }
+
+        self.transaction_log.handle_event(event);
+    }
+
+    fn selected_name(&self) -> Option<String> {
+        self.list_state
+            .selected()
+            .and_then(|index| self.orphans.get(index))
+            .map(|pkg| pkg.name.clone())
+    }
+
+    /// Orphans in `names`, in list order.
+    fn names(&self, names: &HashSet<String>) -> Vec<String> {
+        self.orphans
+            .iter()
+            .filter(|pkg| names.contains(&pkg.name))
+            .map(|pkg| pkg.name.clone())
+            .collect()
+    }
+
+    /// Leave out of the removal the kept orphans and the dependencies they still need.
+    fn update_removals(&mut self) {
+        let keep = self.keep.union(&self.mark_explicit).cloned().collect();
+        self.remove = recursive_orphans(&self.orphans, &keep);
+    }
+
+    fn render_orphans(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(
+            None,
+            Some(" ↑↓ (k/j) | keep (x) | mark explicit (e) ".to_string()),
+            colors,
+        );
+
+        let items: Vec<ListItem> = self
+            .orphans
+            .iter()
+            .map(|pkg| {
+                let status = if self.mark_explicit.contains(&pkg.name) {
+                    "mark explicit"
+                } else if self.keep.contains(&pkg.name) {
+                    "keep"
+                } else if !self.remove.contains(&pkg.name) {
+                    "needed by a kept package"
+                } else {
+                    "remove"
+                };
+
+                ListItem::from(Line::from(format!(
+                    "{} {} ({}) - {status}",
+                    pkg.name,
+                    pkg.version,
+                    to_human_bytes(pkg.size as f64)
+                )))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    fn render_msg_box(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let removed_size: i64 = self
+            .orphans
+            .iter()
+            .filter(|pkg| self.remove.contains(&pkg.name))
+            .map(|pkg| pkg.size)
+            .sum();
+
+        let block = create_block(
+            Some(format!(
+                " orphans: {} of {} to remove, {} freed ",
+                self.remove.len(),
+                self.orphans.len(),
+                to_human_bytes(removed_size as f64)
+            )),
+            None,
+            colors,
+        );
+
+        let message = match self.state {
+            OrphanStates::Confirmation if self.orphans.is_empty() => "No orphans found [ESC]",
+            OrphanStates::Confirmation if self.transaction().is_none() => "Nothing to do [ESC]",
+            OrphanStates::Confirmation => "Clean up orphans? [Enter/ESC]",
+            OrphanStates::Cleaning => "Cleaning up",
+            OrphanStates::Finished(true) => "Clean up finished [ESC]",
+            OrphanStates::Finished(false) => "Clean up failed [ESC]",
+        };
+
+        Paragraph::new(message)
+            .block(block)
+            .centered()
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
+}
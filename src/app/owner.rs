@@ -0,0 +1,162 @@
+use crate::{backend::FileOwner, config::Colors, utils::create_block};
+use crossterm::event::Event;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    widgets::{
+        HighlightSpacing, List, ListItem, ListState, Padding, Paragraph, StatefulWidget, Widget,
+    },
+};
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+/// Prompt looking up the packages owning a file, like `pacman -Qo` and `pacman -F`.
+pub struct OwnerWidget {
+    input: Input,
+    typing: bool,
+    /// Query of the last lookup.
+    query: Option<String>,
+    owners: Vec<FileOwner>,
+    /// The owners of the last query are still being looked up.
+    looking_up: bool,
+    list_state: ListState,
+}
+
+impl Default for OwnerWidget {
+    fn default() -> Self {
+        Self {
+            input: Input::default(),
+            typing: true,
+            query: None,
+            owners: Vec::new(),
+            looking_up: false,
+            list_state: ListState::default(),
+        }
+    }
+}
+
+impl OwnerWidget {
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let [input_area, list_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
+
+        self.render_input(input_area, buf, colors);
+        self.render_owners(list_area, buf, colors);
+    }
+
+    pub fn next(&mut self) {
+        self.list_state.select_next();
+    }
+
+    pub fn previous(&mut self) {
+        self.list_state.select_previous();
+    }
+
+    pub fn is_typing(&self) -> bool {
+        self.typing
+    }
+
+    pub fn start_typing(&mut self) {
+        self.typing = true;
+    }
+
+    /// Path or file name typed.
+    pub fn query(&self) -> &str {
+        self.input.value().trim()
+    }
+
+    pub fn handle_input(&mut self, event: &Event) {
+        self.input.handle_event(event);
+    }
+
+    /// Wait for the owners of the typed query.
+    pub fn start_lookup(&mut self) {
+        self.typing = false;
+        self.query = Some(self.query().to_string());
+        self.owners.clear();
+        self.looking_up = true;
+    }
+
+    /// Show the owners found for the last query.
+    pub fn set_owners(&mut self, owners: Vec<FileOwner>) {
+        self.typing = owners.is_empty();
+        self.looking_up = false;
+        self.owners = owners;
+        self.list_state = ListState::default().with_selected(Some(0));
+    }
+
+    /// Owner under the cursor.
+    pub fn selected(&self) -> Option<&FileOwner> {
+        self.list_state
+            .selected()
+            .and_then(|index| self.owners.get(index))
+    }
+
+    fn render_input(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let legend = if self.typing {
+            " look up [Enter] | cancel [ESC] "
+        } else {
+            " edit (/) "
+        };
+
+        let block = create_block(
+            Some(" owner of path or file name ".to_string()),
+            Some(legend.to_string()),
+            colors,
+        )
+        .padding(Padding::horizontal(3));
+
+        let (border, text) = if self.typing {
+            (colors.ui.key, colors.input.typing)
+        } else {
+            (colors.ui.border, colors.input.normal)
+        };
+
+        let width = area.width.max(3) - 3;
+        let scroll = self.input.visual_scroll(width as usize);
+
+        Paragraph::new(self.input.value())
+            .block(block.border_style(Color::from_u32(border)))
+            .scroll((0, scroll as u16))
+            .style(Color::from_u32(text))
+            .render(area, buf);
+    }
+
+    fn render_owners(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let title = match &self.query {
+            Some(query) if self.looking_up => format!(" looking up {query}… "),
+            Some(query) if self.owners.is_empty() => format!(" no package owns {query} "),
+            Some(_) => format!(" {} owners ", self.owners.len()),
+            None => " owners ".to_string(),
+        };
+
+        let block = create_block(
+            Some(title),
+            Some(" ↑↓ (k/j) | jump [Enter] | close [ESC] ".to_string()),
+            colors,
+        );
+
+        let items: Vec<ListItem> = self
+            .owners
+            .iter()
+            .map(|owner| {
+                let package = match &owner.repository {
+                    Some(repository) => format!("{repository}/{}", owner.package),
+                    None => owner.package.clone(),
+                };
+
+                ListItem::from(format!("{package}  /{}", owner.path))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+}
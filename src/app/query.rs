@@ -0,0 +1,294 @@
+use crate::backend::PackageData;
+use std::{iter::Peekable, str::Chars};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+/// Package field a query term is restricted to, written `field:value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum Qualifier {
+    #[strum(to_string = "name:")]
+    Name,
+    #[strum(to_string = "desc:")]
+    Description,
+    #[strum(to_string = "provides:")]
+    Provides,
+    #[strum(to_string = "dep:")]
+    Dependency,
+    #[strum(to_string = "repo:")]
+    Repository,
+    #[strum(to_string = "license:")]
+    License,
+    #[strum(to_string = "packager:")]
+    Packager,
+    #[strum(to_string = "arch:")]
+    Architecture,
+}
+
+impl Qualifier {
+    /// Split `word` into its qualifier and value, if it starts with a known qualifier.
+    fn split(word: &str) -> Option<(Self, &str)> {
+        Self::iter().find_map(|qualifier| {
+            word.strip_prefix(qualifier.to_string().as_str())
+                .map(|value| (qualifier, value))
+        })
+    }
+
+    /// Values of the field in `pkg`.
+    pub fn values(self, pkg: &PackageData) -> Vec<&str> {
+        match self {
+            Self::Name => vec![pkg.name.as_str()],
+            Self::Description => pkg.description.as_deref().into_iter().collect(),
+            Self::Provides => pkg.provides.iter().map(String::as_str).collect(),
+            Self::Dependency => pkg.dependencies.iter().map(String::as_str).collect(),
+            Self::Repository => pkg.repository.as_deref().into_iter().collect(),
+            Self::License => pkg.licenses.iter().map(String::as_str).collect(),
+            Self::Packager => pkg.packager.as_deref().into_iter().collect(),
+            Self::Architecture => pkg.architecture.as_deref().into_iter().collect(),
+        }
+    }
+}
+
+/// Parsed search query.
+///
+/// Terms separated by spaces must all match, `|` matches either side, `!` negates a
+/// term and parentheses group terms. Unqualified terms are matched against the package
+/// name, qualified ones against the field. Values with spaces or operators can be
+/// quoted, e.g. `desc:"web browser"` or `"^python-(pip|setuptools)$"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Query {
+    /// Matches every package.
+    #[default]
+    All,
+    /// Match on the package name.
+    Name(String),
+    Field(Qualifier, String),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Query {
+    /// Parse a query, leniently so a query being typed is always valid.
+    pub fn parse(query: &str) -> Self {
+        let mut tokens = tokenize(query).into_iter().peekable();
+        let mut queries = Vec::new();
+
+        // Stray closing parentheses are ignored
+        while tokens.peek().is_some() {
+            queries.push(parse_or(&mut tokens));
+            tokens.next_if_eq(&Token::Close);
+        }
+
+        Self::combine(queries, Self::And)
+    }
+
+    /// Qualifiers used in the query.
+    pub fn qualifiers(&self) -> Vec<Qualifier> {
+        let mut qualifiers = Vec::new();
+        self.collect_qualifiers(&mut qualifiers);
+        qualifiers
+    }
+
+    /// Patterns of the terms in the query.
+    pub fn patterns(&self) -> Vec<&str> {
+        match self {
+            Self::All => Vec::new(),
+            Self::Name(pattern) | Self::Field(_, pattern) => vec![pattern.as_str()],
+            Self::Not(query) => query.patterns(),
+            Self::And(queries) | Self::Or(queries) => {
+                queries.iter().flat_map(|query| query.patterns()).collect()
+            }
+        }
+    }
+
+    fn collect_qualifiers(&self, qualifiers: &mut Vec<Qualifier>) {
+        match self {
+            Self::Field(qualifier, _) if !qualifiers.contains(qualifier) => {
+                qualifiers.push(*qualifier)
+            }
+            Self::Not(query) => query.collect_qualifiers(qualifiers),
+            Self::And(queries) | Self::Or(queries) => queries
+                .iter()
+                .for_each(|query| query.collect_qualifiers(qualifiers)),
+            _ => (),
+        }
+    }
+
+    /// Combine queries with `kind`, leaving out empty ones still being typed.
+    fn combine(queries: Vec<Self>, kind: fn(Vec<Self>) -> Self) -> Self {
+        let mut queries: Vec<Self> = queries
+            .into_iter()
+            .filter(|query| *query != Self::All)
+            .collect();
+
+        match queries.len() {
+            0 => Self::All,
+            1 => queries.remove(0),
+            _ => kind(queries),
+        }
+    }
+}
+
+fn parse_or(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Query {
+    let mut queries = vec![parse_and(tokens)];
+
+    while tokens.next_if_eq(&Token::Or).is_some() {
+        queries.push(parse_and(tokens));
+    }
+
+    Query::combine(queries, Query::Or)
+}
+
+fn parse_and(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Query {
+    let mut queries = Vec::new();
+
+    while let Some(token) = tokens.peek()
+        && !matches!(token, Token::Or | Token::Close)
+    {
+        queries.push(parse_not(tokens));
+    }
+
+    Query::combine(queries, Query::And)
+}
+
+fn parse_not(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Query {
+    match tokens.next() {
+        Some(Token::Not) => match parse_not(tokens) {
+            // A lone `!` being typed matches everything
+            Query::All => Query::All,
+            query => Query::Not(Box::new(query)),
+        },
+        Some(Token::Open) => {
+            let query = parse_or(tokens);
+            // Parentheses left open are closed at the end of the query
+            tokens.next_if_eq(&Token::Close);
+            query
+        }
+        Some(Token::Word(word)) => match Qualifier::split(&word) {
+            Some((_, "")) => Query::All,
+            Some((qualifier, value)) => Query::Field(qualifier, value.to_string()),
+            None => Query::Name(word),
+        },
+        _ => Query::All,
+    }
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '|' => tokens.push(Token::Or),
+            '!' => tokens.push(Token::Not),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            char if char.is_whitespace() => (),
+            char => tokens.push(Token::Word(read_word(char, &mut chars))),
+        }
+    }
+
+    tokens
+}
+
+/// Read a word starting with `first`, keeping spaces between quotes.
+fn read_word(first: char, chars: &mut Peekable<Chars>) -> String {
+    let mut word = String::new();
+    let mut quoted = first == '"';
+
+    if !quoted {
+        word.push(first);
+    }
+
+    while let Some(char) = chars.next_if(|char| quoted || !is_separator(*char)) {
+        if char == '"' {
+            quoted = !quoted;
+        } else {
+            word.push(char);
+        }
+    }
+
+    word
+}
+
+fn is_separator(char: char) -> bool {
+    char.is_whitespace() || matches!(char, '|' | '(' | ')')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(pattern: &str) -> Query {
+        Query::Name(pattern.to_string())
+    }
+
+    #[test]
+    fn words_must_all_match() {
+        assert_eq!(Query::parse(""), Query::All);
+        assert_eq!(Query::parse("vim"), name("vim"));
+        assert_eq!(
+            Query::parse("vim  python"),
+            Query::And(vec![name("vim"), name("python")])
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            Query::parse("vim | nano !gui"),
+            Query::Or(vec![
+                name("vim"),
+                Query::And(vec![name("nano"), Query::Not(Box::new(name("gui")))]),
+            ])
+        );
+        assert_eq!(
+            Query::parse("(vim|nano) repo:extra"),
+            Query::And(vec![
+                Query::Or(vec![name("vim"), name("nano")]),
+                Query::Field(Qualifier::Repository, "extra".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn quotes_keep_spaces_and_operators() {
+        assert_eq!(
+            Query::parse(r#"desc:"web browser""#),
+            Query::Field(Qualifier::Description, "web browser".to_string())
+        );
+        assert_eq!(
+            Query::parse(r#""^python-(pip|setuptools)$""#),
+            name("^python-(pip|setuptools)$")
+        );
+    }
+
+    #[test]
+    fn incomplete_queries_are_lenient() {
+        assert_eq!(Query::parse("!"), Query::All);
+        assert_eq!(Query::parse("desc:"), Query::All);
+        assert_eq!(Query::parse("vim |"), name("vim"));
+        assert_eq!(Query::parse("(vim | nano"), Query::parse("vim | nano"));
+        assert_eq!(Query::parse("vim) nano"), Query::parse("vim nano"));
+    }
+
+    #[test]
+    fn qualifiers_and_patterns_are_collected() {
+        let query = Query::parse("dep:glibc !dep:gcc | license:MIT");
+
+        assert_eq!(
+            query.qualifiers(),
+            vec![Qualifier::Dependency, Qualifier::License]
+        );
+        assert_eq!(query.patterns(), vec!["glibc", "gcc", "MIT"]);
+    }
+}
@@ -0,0 +1,85 @@
+use super::transaction::TransactionLog;
+use crate::{backend::TransactionEvent, config::Colors, utils::create_block};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Stylize},
+    widgets::{Paragraph, Widget},
+};
+
+#[derive(Clone, Copy, Default)]
+pub enum RefreshStates {
+    #[default]
+    Refreshing,
+    Finished(bool),
+}
+
+#[derive(Default)]
+pub struct RefreshWidget {
+    state: RefreshStates,
+    transaction_log: TransactionLog,
+}
+
+impl RefreshWidget {
+    pub fn render(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let [msg_area, progress_area, log_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.render_msg_box(msg_area, buf, colors);
+        self.transaction_log
+            .render_progress_bar(progress_area, buf, colors);
+        self.transaction_log.render_log(log_area, buf, colors);
+    }
+
+    pub fn next(&mut self) {
+        self.transaction_log.next();
+    }
+
+    pub fn previous(&mut self) {
+        self.transaction_log.previous();
+    }
+
+    /// Whether the databases are currently being refreshed.
+    pub fn is_refreshing(&self) -> bool {
+        matches!(self.state, RefreshStates::Refreshing)
+    }
+
+    /// Update the log and progress bar with a refresh event.
+    pub fn handle_event(&mut self, event: TransactionEvent) {
+        match event {
+            TransactionEvent::Finished(Ok(_)) => {
+                self.state = RefreshStates::Finished(true);
+                self.transaction_log
+                    .finish("package databases are up to date".to_string());
+            }
+            event => {
+                if let TransactionEvent::Finished(Err(_)) = event {
+                    self.state = RefreshStates::Finished(false);
+                }
+
+                self.transaction_log.handle_event(event);
+            }
+        }
+    }
+
+    fn render_msg_box(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(Some(" refresh databases ".to_string()), None, colors);
+
+        let message = match self.state {
+            RefreshStates::Refreshing => "Refreshing",
+            RefreshStates::Finished(true) => "Refresh finished [ESC]",
+            RefreshStates::Finished(false) => "Refresh failed [ESC]",
+        };
+
+        Paragraph::new(message)
+            .block(block)
+            .centered()
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,140 @@
+use super::transaction::{TransactionLog, summary_text};
+use crate::{
+    backend::{RemoveMode, TransactionEvent, TransactionPackage},
+    config::Colors,
+    error,
+    utils::create_block,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Stylize},
+    text::Text,
+    widgets::{Paragraph, Widget},
+};
+
+#[derive(Clone, Copy, Default)]
+pub enum RemoveStates {
+    #[default]
+    Confirmation,
+    Removing,
+    Finished(bool),
+}
+
+#[derive(Default)]
+pub struct RemoveWidget {
+    state: RemoveStates,
+    mode: RemoveMode,
+    preview: Vec<TransactionPackage>,
+    preview_error: Option<String>,
+    transaction_log: TransactionLog,
+}
+
+impl RemoveWidget {
+    pub fn render(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let [msg_area, progress_area, log_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.render_msg_box(msg_area, buf, colors);
+        self.transaction_log
+            .render_progress_bar(progress_area, buf, colors);
+
+        match self.state {
+            RemoveStates::Confirmation => {
+                self.transaction_log
+                    .render_lines(log_area, buf, colors, self.preview_text())
+            }
+            _ => self.transaction_log.render_log(log_area, buf, colors),
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.transaction_log.next();
+    }
+
+    pub fn previous(&mut self) {
+        self.transaction_log.previous();
+    }
+
+    pub fn mode(&self) -> RemoveMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: RemoveMode) {
+        self.mode = mode;
+    }
+
+    /// Set the packages the removal would take away, or why it cannot be done.
+    pub fn set_preview(&mut self, preview: error::Result<Vec<TransactionPackage>>) {
+        match preview {
+            Ok(packages) => {
+                self.preview = packages;
+                self.preview_error = None;
+            }
+            Err(err) => {
+                self.preview.clear();
+                self.preview_error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Whether the removal can be confirmed.
+    pub fn can_remove(&self) -> bool {
+        matches!(self.state, RemoveStates::Confirmation)
+            && self.preview_error.is_none()
+            && !self.preview.is_empty()
+    }
+
+    /// Whether a transaction is currently running.
+    pub fn is_removing(&self) -> bool {
+        matches!(self.state, RemoveStates::Removing)
+    }
+
+    pub fn start_removal(&mut self) {
+        self.state = RemoveStates::Removing;
+        self.transaction_log = Default::default();
+    }
+
+    /// Update the log and progress bar with a transaction event.
+    pub fn handle_event(&mut self, event: TransactionEvent) {
+        if let TransactionEvent::Finished(result) = &event {
+            self.state = RemoveStates::Finished(result.is_ok());
+        }
+
+        self.transaction_log.handle_event(event);
+    }
+
+    fn preview_text(&self) -> Text<'static> {
+        match &self.preview_error {
+            Some(error) => Text::from(format!("error: {error}")),
+            None => summary_text(&self.preview),
+        }
+    }
+
+    fn render_msg_box(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(
+            Some(format!(" {}: {} ", self.mode, self.mode.description())),
+            Some(" ⇄ mode (tab / shift+tab) ".to_string()),
+            colors,
+        );
+
+        let message = match self.state {
+            RemoveStates::Confirmation if self.can_remove() => "Remove packages? [Enter/ESC]",
+            RemoveStates::Confirmation => "Cannot remove packages [ESC]",
+            RemoveStates::Removing => "Removing",
+            RemoveStates::Finished(true) => "Removal finished [ESC]",
+            RemoveStates::Finished(false) => "Removal failed [ESC]",
+        };
+
+        Paragraph::new(message)
+            .block(block)
+            .centered()
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
+}
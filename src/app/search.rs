@@ -0,0 +1,183 @@
+use super::query::Query;
+use crate::{backend::PackageData, config::TieBreaker};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use regex::{Regex, RegexBuilder};
+use std::{cmp::Ordering, collections::HashMap, ops::Range};
+use strum_macros::{Display, FromRepr};
+
+/// Package matching the search.
+pub struct SearchHit {
+    /// Position of the package in the snapshot.
+    pub position: usize,
+    pub score: i64,
+    /// Indices of the matched characters in the package name.
+    pub indices: Vec<usize>,
+}
+
+/// How the terms of a query match package names and fields.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, FromRepr)]
+#[strum(serialize_all = "lowercase")]
+pub enum SearchMode {
+    /// Fuzzy match on names, ranked by score. Fields are matched by substring.
+    #[default]
+    Fuzzy,
+    /// Case insensitive substring.
+    Substring,
+    /// Case insensitive regular expression.
+    Regex,
+    /// Whole name or field.
+    Exact,
+}
+
+impl SearchMode {
+    /// Get next mode, wrapping around.
+    pub fn next(self) -> Self {
+        Self::from_repr(self as usize + 1).unwrap_or_default()
+    }
+}
+
+/// Search over the package fields, ranked by the fuzzy score of the name terms.
+#[derive(Default)]
+pub struct Search {
+    matcher: SkimMatcherV2,
+    tie_breaker: Option<TieBreaker>,
+    mode: SearchMode,
+    /// Compiled patterns of the query, in regex mode.
+    regexes: HashMap<String, Regex>,
+}
+
+impl Search {
+    pub fn new(tie_breaker: Option<TieBreaker>) -> Self {
+        Self {
+            tie_breaker,
+            ..Default::default()
+        }
+    }
+
+    pub fn mode(&self) -> SearchMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: SearchMode) {
+        self.mode = mode;
+    }
+
+    /// Compile the patterns of `query` for the current mode.
+    pub fn prepare(&mut self, query: &Query) -> Result<(), regex::Error> {
+        self.regexes.clear();
+
+        if self.mode == SearchMode::Regex {
+            for pattern in query.patterns() {
+                let regex = RegexBuilder::new(pattern).case_insensitive(true).build()?;
+                self.regexes.insert(pattern.to_string(), regex);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Packages matching `query`, best fuzzy matches first.
+    ///
+    /// An empty query matches every package. Fuzzy matches are ranked by score, then by the
+    /// tie breaker if set. Other modes keep the order of `packages`, which is the list order.
+    pub fn hits<'a>(
+        &self,
+        packages: impl Iterator<Item = (usize, &'a PackageData)>,
+        query: &Query,
+    ) -> Vec<SearchHit> {
+        let mut hits: Vec<(SearchHit, &PackageData)> = packages
+            .filter_map(|(position, pkg)| {
+                let (score, indices) = self.matches(query, pkg)?;
+
+                Some((
+                    SearchHit {
+                        position,
+                        score,
+                        indices,
+                    },
+                    pkg,
+                ))
+            })
+            .collect();
+
+        if self.mode == SearchMode::Fuzzy && *query != Query::All {
+            hits.sort_by(|(a, a_pkg), (b, b_pkg)| {
+                b.score
+                    .cmp(&a.score)
+                    .then_with(|| self.break_tie(a_pkg, b_pkg))
+            });
+        }
+
+        hits.into_iter().map(|(hit, _)| hit).collect()
+    }
+
+    /// Score and matched name indices of `pkg`, if it matches `query`.
+    fn matches(&self, query: &Query, pkg: &PackageData) -> Option<(i64, Vec<usize>)> {
+        match query {
+            Query::All => Some((0, Vec::new())),
+            Query::Name(pattern) if self.mode == SearchMode::Fuzzy => {
+                self.matcher.fuzzy_indices(&pkg.name, pattern)
+            }
+            Query::Name(pattern) => self.find(&pkg.name, pattern).map(|indices| (0, indices)),
+            Query::Field(qualifier, pattern) => qualifier
+                .values(pkg)
+                .iter()
+                .any(|field| self.find(field, pattern).is_some())
+                .then(|| (0, Vec::new())),
+            Query::Not(query) => match self.matches(query, pkg) {
+                Some(_) => None,
+                None => Some((0, Vec::new())),
+            },
+            Query::And(queries) => {
+                queries
+                    .iter()
+                    .try_fold((0, Vec::new()), |(score, mut indices), query| {
+                        let (term_score, term_indices) = self.matches(query, pkg)?;
+                        indices.extend(term_indices);
+                        Some((score + term_score, indices))
+                    })
+            }
+            Query::Or(queries) => queries
+                .iter()
+                .filter_map(|query| self.matches(query, pkg))
+                .max_by_key(|(score, _)| *score),
+        }
+    }
+
+    /// Indices of the characters of `text` matched by `pattern`, without fuzzy matching.
+    fn find(&self, text: &str, pattern: &str) -> Option<Vec<usize>> {
+        match self.mode {
+            SearchMode::Fuzzy | SearchMode::Substring => {
+                let text = text.to_lowercase();
+                let pattern = pattern.to_lowercase();
+                let start = text.find(&pattern)?;
+
+                Some(char_indices(&text, start..start + pattern.len()))
+            }
+            SearchMode::Regex => {
+                let found = self.regexes.get(pattern)?.find(text)?;
+
+                Some(char_indices(text, found.range()))
+            }
+            SearchMode::Exact => (text == pattern).then(|| char_indices(text, 0..text.len())),
+        }
+    }
+
+    fn break_tie(&self, a: &PackageData, b: &PackageData) -> Ordering {
+        match self.tie_breaker {
+            Some(TieBreaker::Length) => a.name.len().cmp(&b.name.len()),
+            Some(TieBreaker::Name) => a.name.cmp(&b.name),
+            Some(TieBreaker::Size) => b.size.cmp(&a.size),
+            Some(TieBreaker::DbOrder) | None => Ordering::Equal,
+        }
+    }
+}
+
+/// Indices of the characters of `text` in the byte range `bytes`.
+fn char_indices(text: &str, bytes: Range<usize>) -> Vec<usize> {
+    text.char_indices()
+        .enumerate()
+        .filter(|(_, (byte, _))| bytes.contains(byte))
+        .map(|(index, _)| index)
+        .collect()
+}
@@ -0,0 +1,80 @@
+use crate::{
+    backend::PackageData,
+    config::{SortKey, SortOrder},
+};
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Packages of a view, copied once from the backend and indexed by name.
+#[derive(Default)]
+pub struct PackageSnapshot {
+    packages: Vec<PackageData>,
+    index: HashMap<String, usize>,
+}
+
+impl PackageSnapshot {
+    pub fn new(packages: impl IntoIterator<Item = PackageData>) -> Self {
+        let packages: Vec<PackageData> = packages.into_iter().collect();
+        let index = packages
+            .iter()
+            .enumerate()
+            .map(|(position, pkg)| (pkg.name.clone(), position))
+            .collect();
+
+        Self { packages, index }
+    }
+
+    pub fn packages(&self) -> &[PackageData] {
+        &self.packages
+    }
+
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    /// Package at `position` in the snapshot.
+    pub fn get(&self, position: usize) -> Option<&PackageData> {
+        self.packages.get(position)
+    }
+
+    /// Position of the package named `name`.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    /// Positions of the packages in `sort` order, database order breaking ties.
+    pub fn sorted(&self, sort: SortOrder) -> Vec<usize> {
+        let mut positions: Vec<usize> = (0..self.packages.len()).collect();
+
+        // Every package is equal in database order, which can only be reversed
+        if sort.key == SortKey::Database {
+            if sort.descending {
+                positions.reverse();
+            }
+
+            return positions;
+        }
+
+        positions.sort_by(|a, b| {
+            let ordering = compare(sort.key, &self.packages[*a], &self.packages[*b]);
+            if sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+
+        positions
+    }
+}
+
+fn compare(key: SortKey, a: &PackageData, b: &PackageData) -> Ordering {
+    match key {
+        SortKey::Database => Ordering::Equal,
+        SortKey::Name => a.name.cmp(&b.name),
+        SortKey::Size => a.size.cmp(&b.size),
+        SortKey::InstallDate => a.install_date.cmp(&b.install_date),
+        SortKey::BuildDate => a.build_date.cmp(&b.build_date),
+        SortKey::Repository => a.repository.cmp(&b.repository),
+        SortKey::UpgradesFirst => b.new_version.is_some().cmp(&a.new_version.is_some()),
+    }
+}
@@ -1,9 +1,14 @@
-use crate::{config::Colors, utils::create_block};
+use super::transaction::{TransactionLog, stale_upgrades_warning};
+use crate::{
+    backend::{PartialUpgrade, TransactionEvent},
+    config::Colors,
+    utils::create_block,
+};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Flex, Layout, Rect},
     style::{Color, Stylize},
-    text::Text,
+    text::{Line, Text},
     widgets::{Paragraph, Widget},
 };
 
@@ -12,12 +17,16 @@ pub enum SyncStates {
     #[default]
     Confirmation,
     Syncing,
+    Finished(bool),
 }
 
 #[derive(Default)]
 pub struct SyncWidget {
     state: SyncStates,
-    vertical_scroll: i16,
+    partial_upgrade: PartialUpgrade,
+    /// Selected upgrades the system databases do not know yet.
+    stale_upgrades: Vec<String>,
+    transaction_log: TransactionLog,
 }
 
 impl SyncWidget {
@@ -28,11 +37,28 @@ impl SyncWidget {
         colors: &Colors,
         packages: impl IntoIterator<Item = &'a str>,
     ) {
-        let [msg_area, log_area] =
-            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
+        let [msg_area, progress_area, log_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
 
         self.render_msg_box(msg_area, buf, colors);
-        self.render_log_box(log_area, buf, colors, packages);
+        self.transaction_log
+            .render_progress_bar(progress_area, buf, colors);
+
+        match self.state {
+            SyncStates::Confirmation => {
+                let mut lines = stale_upgrades_warning(&self.stale_upgrades, colors);
+                lines.extend(self.partial_upgrade_warning(colors));
+                lines.extend(packages.into_iter().map(Line::from));
+
+                self.transaction_log
+                    .render_lines(log_area, buf, colors, Text::from(lines))
+            }
+            _ => self.transaction_log.render_log(log_area, buf, colors),
+        }
     }
 
     pub fn area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
@@ -46,23 +72,106 @@ impl SyncWidget {
     }
 
     pub fn next(&mut self) {
-        self.vertical_scroll = 1;
+        self.transaction_log.next();
     }
 
     pub fn previous(&mut self) {
-        self.vertical_scroll = -1;
+        self.transaction_log.previous();
+    }
+
+    /// Set the partial upgrade the selection would cause, to warn about it.
+    pub fn set_partial_upgrade(&mut self, partial_upgrade: PartialUpgrade) {
+        self.partial_upgrade = partial_upgrade;
+    }
+
+    /// Set the selected upgrades the system databases do not know yet, to warn about them.
+    pub fn set_stale_upgrades(&mut self, stale_upgrades: Vec<String>) {
+        self.stale_upgrades = stale_upgrades;
+    }
+
+    /// Whether the system databases must be refreshed to sync the selected upgrades.
+    pub fn has_stale_upgrades(&self) -> bool {
+        !self.stale_upgrades.is_empty()
+    }
+
+    /// Whether a transaction is currently running.
+    pub fn is_syncing(&self) -> bool {
+        matches!(self.state, SyncStates::Syncing)
     }
 
     pub fn start_sync(&mut self) {
-        self.state = SyncStates::Syncing;
+        *self = Self {
+            state: SyncStates::Syncing,
+            ..Default::default()
+        };
+    }
+
+    /// Update the log and progress bar with a transaction event.
+    pub fn handle_event(&mut self, event: TransactionEvent) {
+        if let TransactionEvent::Finished(result) = &event {
+            self.state = SyncStates::Finished(result.is_ok());
+        }
+
+        self.transaction_log.handle_event(event);
+    }
+
+    fn partial_upgrade_warning(&self, colors: &Colors) -> Vec<Line<'static>> {
+        if !self.partial_upgrade.is_partial() {
+            return Vec::new();
+        }
+
+        let warning = Color::from_u32(colors.text.warning);
+        let mut lines = vec![
+            Line::from("warning: partial upgrades are not supported and may break the system")
+                .fg(warning)
+                .bold(),
+        ];
+
+        if !self.partial_upgrade.skipped_upgrades.is_empty() {
+            lines.push(
+                Line::from(format!(
+                    "upgrades left out: {}",
+                    self.partial_upgrade.skipped_upgrades.join(", ")
+                ))
+                .fg(warning),
+            );
+        }
+
+        for (dependency, dependent) in &self.partial_upgrade.outdated_dependencies {
+            lines.push(
+                Line::from(format!("{dependent} depends on outdated {dependency}")).fg(warning),
+            );
+        }
+
+        lines.push(Line::default());
+        lines
     }
 
     fn render_msg_box(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
         let block = create_block(None, None, colors);
 
         let message = match self.state {
+            SyncStates::Confirmation
+                if self.has_stale_upgrades() || self.partial_upgrade.is_partial() =>
+            {
+                let message = if self.has_stale_upgrades() {
+                    "Outdated system databases! Refresh [shift+y] | sync anyway [Enter] | cancel [ESC]"
+                } else {
+                    "Partial upgrade! Sync anyway [Enter] | full upgrade [shift+u] | cancel [ESC]"
+                };
+
+                return Paragraph::new(message)
+                    .block(block)
+                    .centered()
+                    .bold()
+                    .bg(Color::from_u32(colors.ui.background))
+                    .fg(Color::from_u32(colors.text.warning))
+                    .render(area, buf);
+            }
             SyncStates::Confirmation => "Sync packages? [Enter/ESC]",
             SyncStates::Syncing => "Syncing",
+            SyncStates::Finished(true) => "Sync finished [ESC]",
+            SyncStates::Finished(false) => "Sync failed [ESC]",
         };
 
         Paragraph::new(message)
@@ -72,24 +181,4 @@ impl SyncWidget {
             .fg(Color::from_u32(colors.text.text))
             .render(area, buf);
     }
-
-    fn render_log_box<'a>(
-        &self,
-        area: Rect,
-        buf: &mut Buffer,
-        colors: &Colors,
-        packages: impl IntoIterator<Item = &'a str>,
-    ) {
-        let block = create_block(None, None, colors);
-        let packages = Text::from_iter(packages);
-        let scroll = (packages.height() as u16).saturating_sub(area.height);
-        let scroll = scroll.saturating_add_signed(self.vertical_scroll);
-
-        Paragraph::new(packages)
-            .block(block)
-            .bg(Color::from_u32(colors.ui.background))
-            .fg(Color::from_u32(colors.text.text))
-            .scroll((scroll, 0))
-            .render(area, buf);
-    }
 }
@@ -0,0 +1,48 @@
+use crate::{
+    backend::PackageData,
+    config::{Column, ColumnConfig, SortOrder},
+    utils::to_human_bytes,
+};
+use ratatui::{
+    layout::Constraint,
+    text::Line,
+    widgets::{Cell, Row},
+};
+
+/// Header of the package table, marking the column the list is sorted by.
+pub fn header(columns: &[ColumnConfig], sort: SortOrder) -> Row<'static> {
+    Row::new(columns.iter().map(|config| {
+        if config.column.sort_key() == Some(sort.key) {
+            let direction = if sort.descending { "↓" } else { "↑" };
+            Cell::from(format!("{} {direction}", config.column))
+        } else {
+            Cell::from(config.column.to_string())
+        }
+    }))
+}
+
+/// Row of the package table, with `name` as the name cell.
+pub fn row(columns: &[ColumnConfig], pkg: &PackageData, name: Line<'static>) -> Row<'static> {
+    Row::new(columns.iter().map(|config| {
+        match config.column {
+            Column::Name => Cell::from(name.clone()),
+            Column::Version => Cell::from(pkg.version.clone()),
+            Column::NewVersion => Cell::from(pkg.new_version.clone().unwrap_or_default()),
+            Column::Size => Cell::from(to_human_bytes(pkg.size as f64)),
+            Column::Repository => Cell::from(pkg.repository.clone().unwrap_or_default()),
+            Column::InstallDate => Cell::from(
+                pkg.install_date
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default(),
+            ),
+        }
+    }))
+}
+
+/// Widths of the columns, columns without a width sharing the remaining space.
+pub fn widths(columns: &[ColumnConfig]) -> Vec<Constraint> {
+    columns
+        .iter()
+        .map(|config| config.width.map_or(Constraint::Fill(1), Constraint::Length))
+        .collect()
+}
@@ -1,5 +1,5 @@
 use super::create_block;
-use crate::{config::Colors, pacman::PackageData};
+use crate::{backend::PackageData, config::Colors};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -20,6 +20,12 @@ pub enum DependenciesTabs {
     Conflics,
     #[strum(to_string = "replaces")]
     Replaces,
+    #[strum(to_string = "required by")]
+    RequiredBy,
+    #[strum(to_string = "optional for")]
+    OptionalFor,
+    #[strum(to_string = "files")]
+    Files,
 }
 
 impl DependenciesTabs {
@@ -30,6 +36,10 @@ impl DependenciesTabs {
             Self::OptDeps => self.render_opt_deps_tab(area, buf, package, colors),
             Self::Conflics => self.render_conflicts_tab(area, buf, package, colors),
             Self::Replaces => self.render_replaces_tab(area, buf, package, colors),
+            Self::RequiredBy => self.render_required_by_tab(area, buf, package, colors),
+            Self::OptionalFor => self.render_optional_for_tab(area, buf, package, colors),
+            // Rendered by the files widget, which keeps its own state
+            Self::Files => (),
         }
     }
 
@@ -60,7 +70,7 @@ impl DependenciesTabs {
         let deps_lines: Vec<Line> = package
             .dependencies
             .iter()
-            .map(|dep| Line::from(dep.to_string()))
+            .map(|dep| Line::from(dep.as_str()))
             .collect();
 
         Paragraph::new(deps_lines)
@@ -82,7 +92,7 @@ impl DependenciesTabs {
         let opt_deps_lines: Vec<Line> = package
             .optional_dependencies
             .iter()
-            .map(|dep| Line::from(dep.to_string()))
+            .map(|dep| Line::from(dep.as_str()))
             .collect();
 
         Paragraph::new(opt_deps_lines)
@@ -104,7 +114,7 @@ impl DependenciesTabs {
         let conflicts_lines: Vec<Line> = package
             .conflicts
             .iter()
-            .map(|dep| Line::from(dep.to_string()))
+            .map(|dep| Line::from(dep.as_str()))
             .collect();
 
         Paragraph::new(conflicts_lines)
@@ -126,7 +136,7 @@ impl DependenciesTabs {
         let replaces_lines: Vec<Line> = package
             .replaces
             .iter()
-            .map(|dep| Line::from(dep.to_string()))
+            .map(|dep| Line::from(dep.as_str()))
             .collect();
 
         Paragraph::new(replaces_lines)
@@ -135,4 +145,48 @@ impl DependenciesTabs {
             .fg(Color::from_u32(colors.text.text))
             .render(area, buf);
     }
+
+    fn render_required_by_tab(
+        self,
+        area: Rect,
+        buf: &mut Buffer,
+        package: &PackageData,
+        colors: &Colors,
+    ) {
+        let block = create_block(None, Some(" ⇄ (tab / shift+tab) ".to_string()), colors);
+
+        let required_by_lines: Vec<Line> = package
+            .required_by
+            .iter()
+            .map(|dependent| Line::from(dependent.as_str()))
+            .collect();
+
+        Paragraph::new(required_by_lines)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
+
+    fn render_optional_for_tab(
+        self,
+        area: Rect,
+        buf: &mut Buffer,
+        package: &PackageData,
+        colors: &Colors,
+    ) {
+        let block = create_block(None, Some(" ⇄ (tab / shift+tab) ".to_string()), colors);
+
+        let optional_for_lines: Vec<Line> = package
+            .optional_for
+            .iter()
+            .map(|dependent| Line::from(dependent.as_str()))
+            .collect();
+
+        Paragraph::new(optional_for_lines)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
 }
@@ -0,0 +1,210 @@
+use crate::{
+    backend::{TransactionEvent, TransactionPackage},
+    config::Colors,
+    utils::{create_block, to_human_bytes},
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Gauge, Paragraph, Widget},
+};
+use std::collections::HashMap;
+
+/// Log and progress of a transaction, shared by the transaction popups.
+#[derive(Default)]
+pub struct TransactionLog {
+    vertical_scroll: i16,
+    log: Vec<String>,
+    downloads: HashMap<String, (i64, i64)>,
+    progress: f64,
+    progress_label: String,
+}
+
+impl TransactionLog {
+    pub fn next(&mut self) {
+        self.vertical_scroll = self.vertical_scroll.saturating_add(1).min(0);
+    }
+
+    pub fn previous(&mut self) {
+        self.vertical_scroll = self.vertical_scroll.saturating_sub(1);
+    }
+
+    /// Update the log and progress bar with a transaction event.
+    pub fn handle_event(&mut self, event: TransactionEvent) {
+        match event {
+            TransactionEvent::Log(line) => self.log.push(line),
+            TransactionEvent::Download {
+                file,
+                downloaded,
+                total,
+            } => {
+                self.downloads.insert(file, (downloaded, total));
+
+                let (downloaded, total) = self.downloads.values().fold(
+                    (0, 0),
+                    |(downloaded, total), (file_downloaded, file_total)| {
+                        (downloaded + file_downloaded, total + file_total)
+                    },
+                );
+
+                self.progress = ratio(downloaded as f64, total as f64);
+                self.progress_label = format!(
+                    "downloading {} / {}",
+                    to_human_bytes(downloaded as f64),
+                    to_human_bytes(total as f64)
+                );
+            }
+            TransactionEvent::Progress {
+                operation,
+                package,
+                percent,
+                current,
+                total,
+            } => {
+                let done = current.saturating_sub(1) as f64 + f64::from(percent) / 100.0;
+
+                self.progress = ratio(done, total as f64);
+                self.progress_label = if package.is_empty() {
+                    format!("{operation} ({current}/{total})")
+                } else {
+                    format!("{operation} {package} ({current}/{total})")
+                };
+            }
+            TransactionEvent::Finished(result) => self.finish(match result {
+                Ok(packages) if packages.is_empty() => "there is nothing to do".to_string(),
+                Ok(packages) => format!("transaction completed for {} packages", packages.len()),
+                Err(error) => format!("error: {error}"),
+            }),
+        }
+    }
+
+    /// Complete the progress bar with a last log line.
+    pub fn finish(&mut self, message: String) {
+        self.log.push(message);
+        self.progress = 1.0;
+        self.progress_label = "done".to_string();
+    }
+
+    pub fn render_progress_bar(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(None, None, colors);
+
+        Gauge::default()
+            .block(block)
+            .gauge_style(
+                Style::new()
+                    .fg(Color::from_u32(colors.ui.key))
+                    .bg(Color::from_u32(colors.ui.background)),
+            )
+            .ratio(self.progress)
+            .label(self.progress_label.as_str())
+            .render(area, buf);
+    }
+
+    /// Render the transaction log.
+    pub fn render_log(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        self.render_lines(
+            area,
+            buf,
+            colors,
+            Text::from_iter(self.log.iter().map(String::as_ref)),
+        );
+    }
+
+    /// Render arbitrary lines in the log box, scrolled to the bottom by default.
+    pub fn render_lines(&self, area: Rect, buf: &mut Buffer, colors: &Colors, lines: Text) {
+        let block = create_block(None, Some(" ↑↓ (k/j) ".to_string()), colors);
+        let scroll = (lines.height() as u16).saturating_sub(area.height.saturating_sub(2));
+        let scroll = scroll.saturating_add_signed(self.vertical_scroll);
+
+        Paragraph::new(lines)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .scroll((scroll, 0))
+            .render(area, buf);
+    }
+}
+
+/// Summary of the packages affected by a transaction, with size totals.
+pub fn summary_text(packages: &[TransactionPackage]) -> Text<'static> {
+    let mut lines: Vec<Line> = packages
+        .iter()
+        .map(|pkg| {
+            let change = match (&pkg.old_version, &pkg.new_version) {
+                (Some(old), Some(new)) => format!("{old} → {new}"),
+                (None, Some(new)) => format!("{new} (new)"),
+                (Some(old), None) => format!("{old} (removed)"),
+                (None, None) => String::new(),
+            };
+
+            Line::from(format!(
+                "{} {} [{}]",
+                pkg.name,
+                change,
+                signed_bytes(pkg.new_size - pkg.old_size)
+            ))
+        })
+        .collect();
+
+    let download_size: i64 = packages.iter().map(|pkg| pkg.download_size).sum();
+    let size_delta: i64 = packages.iter().map(|pkg| pkg.new_size - pkg.old_size).sum();
+
+    lines.push(Line::default());
+    lines.push(Line::from(format!("Packages: {}", packages.len())));
+    if download_size > 0 {
+        lines.push(Line::from(format!(
+            "Total download size: {}",
+            to_human_bytes(download_size as f64)
+        )));
+    }
+    lines.push(Line::from(format!(
+        "Net installed size: {}",
+        signed_bytes(size_delta)
+    )));
+
+    Text::from(lines)
+}
+
+/// Warning about the upgrades the system databases do not know yet, which a transaction
+/// would not install.
+pub fn stale_upgrades_warning(stale_upgrades: &[String], colors: &Colors) -> Vec<Line<'static>> {
+    if stale_upgrades.is_empty() {
+        return Vec::new();
+    }
+
+    let warning = Color::from_u32(colors.text.warning);
+
+    vec![
+        Line::from("warning: the system databases are older than the list")
+            .fg(warning)
+            .bold(),
+        Line::from(format!(
+            "upgrades they do not know yet: {}",
+            stale_upgrades.join(", ")
+        ))
+        .fg(warning),
+        Line::from("refresh them like pacman -Sy with [shift+y] to install these upgrades")
+            .fg(warning),
+        Line::default(),
+    ]
+}
+
+/// Human readable size prefixed with its sign.
+fn signed_bytes(bytes: i64) -> String {
+    if bytes < 0 {
+        format!("-{}", to_human_bytes(bytes.unsigned_abs() as f64))
+    } else {
+        format!("+{}", to_human_bytes(bytes as f64))
+    }
+}
+
+/// Ratio clamped to the range a progress bar accepts.
+fn ratio(done: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        (done / total).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
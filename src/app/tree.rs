@@ -0,0 +1,327 @@
+use crate::{
+    backend::{PackageBackend, PackageData},
+    config::Colors,
+    utils::{create_block, to_human_bytes},
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{HighlightSpacing, List, ListItem, ListState, StatefulWidget},
+};
+use std::collections::{HashMap, HashSet};
+use strum_macros::Display;
+
+/// Direction the dependency tree is walked in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display)]
+pub enum TreeDirection {
+    /// Packages the root depends on, like `pactree`.
+    #[default]
+    #[strum(to_string = "dependencies")]
+    Dependencies,
+    /// Packages depending on the root, like `pactree -r`.
+    #[strum(to_string = "required by")]
+    RequiredBy,
+}
+
+impl TreeDirection {
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Dependencies => Self::RequiredBy,
+            Self::RequiredBy => Self::Dependencies,
+        }
+    }
+
+    /// Dependencies of `pkg` walked in this direction.
+    fn children(self, pkg: &PackageData) -> &[String] {
+        match self {
+            Self::Dependencies => &pkg.dependencies,
+            Self::RequiredBy => &pkg.required_by,
+        }
+    }
+}
+
+/// Row of the flattened tree.
+struct Node {
+    depth: usize,
+    /// Dependency as written by the parent, e.g. `sh` or `glibc>=2.27`.
+    dep: String,
+    /// Package satisfying the dependency, `None` if nothing does.
+    package: Option<String>,
+    /// The package is already one of the ancestors.
+    cycle: bool,
+    expanded: bool,
+}
+
+/// Collapsible dependency tree of a package, like `pactree`.
+#[derive(Default)]
+pub struct DependencyTree {
+    direction: TreeDirection,
+    /// Visible rows, children right after their parent.
+    nodes: Vec<Node>,
+    /// Package satisfying each dependency met so far.
+    satisfiers: HashMap<String, Option<String>>,
+    packages: HashMap<String, PackageData>,
+    /// Size of the packages reachable from a package, itself included.
+    subtree_sizes: HashMap<String, i64>,
+    list_state: ListState,
+}
+
+impl DependencyTree {
+    /// Tree of `root` with its first level expanded.
+    pub fn new(backend: &dyn PackageBackend, root: &PackageData, direction: TreeDirection) -> Self {
+        let mut tree = Self {
+            direction,
+            list_state: ListState::default().with_selected(Some(0)),
+            ..Default::default()
+        };
+
+        tree.packages.insert(root.name.clone(), root.clone());
+        tree.nodes.push(Node {
+            depth: 0,
+            dep: root.name.clone(),
+            package: Some(root.name.clone()),
+            cycle: false,
+            expanded: false,
+        });
+        tree.subtree_size(backend, &root.name);
+        tree.expand(backend, 0);
+
+        tree
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(
+            Some(format!(" {} tree ", self.direction)),
+            Some(" ↑↓ (k/j) | expand [Enter] | ⇄ direction (tab) ".to_string()),
+            colors,
+        );
+
+        let items: Vec<ListItem> = self
+            .nodes
+            .iter()
+            .map(|node| ListItem::from(self.node_line(node, colors)))
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(" → ")
+            .highlight_style(Style::new().fg(Color::from_u32(colors.ui.key)));
+
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    pub fn next(&mut self) {
+        self.list_state.select_next();
+    }
+
+    pub fn previous(&mut self) {
+        self.list_state.select_previous();
+    }
+
+    pub fn first(&mut self) {
+        self.list_state.select_first();
+    }
+
+    pub fn last(&mut self) {
+        self.list_state.select_last();
+    }
+
+    /// Expand or collapse the row under the cursor.
+    pub fn toggle(&mut self, backend: &dyn PackageBackend) {
+        let Some(index) = self
+            .list_state
+            .selected()
+            .filter(|index| *index < self.nodes.len())
+        else {
+            return;
+        };
+
+        if self.nodes[index].expanded {
+            self.collapse(index);
+        } else {
+            self.expand(backend, index);
+        }
+    }
+
+    /// Walk the tree of the same root in the other direction.
+    pub fn switch_direction(&mut self, backend: &dyn PackageBackend) {
+        if let Some(root) = self
+            .nodes
+            .first()
+            .and_then(|node| node.package.as_ref())
+            .and_then(|name| self.packages.get(name))
+        {
+            *self = Self::new(backend, &root.clone(), self.direction.toggle());
+        }
+    }
+
+    fn expand(&mut self, backend: &dyn PackageBackend, index: usize) {
+        let node = &self.nodes[index];
+
+        if node.cycle {
+            return;
+        }
+
+        let Some(deps) = node
+            .package
+            .as_ref()
+            .and_then(|name| self.packages.get(name))
+            .map(|pkg| self.direction.children(pkg).to_vec())
+            .filter(|deps| !deps.is_empty())
+        else {
+            return;
+        };
+
+        let depth = node.depth + 1;
+        let ancestors = self.ancestors(index);
+        let mut children = Vec::new();
+
+        for dep in deps {
+            let package = self.resolve(backend, &dep);
+
+            if let Some(name) = &package {
+                self.subtree_size(backend, name);
+            }
+
+            children.push(Node {
+                depth,
+                cycle: package
+                    .as_ref()
+                    .is_some_and(|name| ancestors.contains(name)),
+                dep,
+                package,
+                expanded: false,
+            });
+        }
+
+        self.nodes[index].expanded = true;
+        self.nodes.splice(index + 1..index + 1, children);
+    }
+
+    fn collapse(&mut self, index: usize) {
+        let depth = self.nodes[index].depth;
+        let end = self.nodes[index + 1..]
+            .iter()
+            .position(|node| node.depth <= depth)
+            .map_or(self.nodes.len(), |position| index + 1 + position);
+
+        self.nodes.drain(index + 1..end);
+        self.nodes[index].expanded = false;
+    }
+
+    /// Packages of the row at `index` and of its ancestors.
+    fn ancestors(&self, index: usize) -> HashSet<String> {
+        let mut depth = self.nodes[index].depth + 1;
+
+        self.nodes[..=index]
+            .iter()
+            .rev()
+            .filter(|node| {
+                let ancestor = node.depth < depth;
+                depth = depth.min(node.depth);
+                ancestor
+            })
+            .filter_map(|node| node.package.clone())
+            .collect()
+    }
+
+    /// Name of the package satisfying `dep`, resolving virtual provides.
+    fn resolve(&mut self, backend: &dyn PackageBackend, dep: &str) -> Option<String> {
+        if let Some(name) = self.satisfiers.get(dep) {
+            return name.clone();
+        }
+
+        let name = backend.satisfier(dep).map(|pkg| {
+            let name = pkg.name.clone();
+            self.packages.entry(name.clone()).or_insert(pkg);
+            name
+        });
+
+        self.satisfiers.insert(dep.to_string(), name.clone());
+        name
+    }
+
+    /// Size of the packages reachable from `name`, each counted once.
+    fn subtree_size(&mut self, backend: &dyn PackageBackend, name: &str) -> i64 {
+        if let Some(size) = self.subtree_sizes.get(name) {
+            return *size;
+        }
+
+        let mut seen = HashSet::from([name.to_string()]);
+        let mut stack = vec![name.to_string()];
+        let mut size = 0;
+
+        while let Some(current) = stack.pop() {
+            let Some(deps) = self.packages.get(&current).map(|pkg| {
+                size += pkg.size;
+                self.direction.children(pkg).to_vec()
+            }) else {
+                continue;
+            };
+
+            for dep in deps {
+                if let Some(dep_name) = self.resolve(backend, &dep)
+                    && seen.insert(dep_name.clone())
+                {
+                    stack.push(dep_name);
+                }
+            }
+        }
+
+        self.subtree_sizes.insert(name.to_string(), size);
+        size
+    }
+
+    fn node_line(&self, node: &Node, colors: &Colors) -> Line<'static> {
+        let marker = match &node.package {
+            _ if node.expanded => "▾ ",
+            Some(name)
+                if !node.cycle
+                    && self
+                        .packages
+                        .get(name)
+                        .is_some_and(|pkg| !self.direction.children(pkg).is_empty()) =>
+            {
+                "▸ "
+            }
+            _ => "  ",
+        };
+
+        let mut spans = vec![Span::from(format!("{}{marker}", "  ".repeat(node.depth)))];
+
+        match &node.package {
+            Some(name) => {
+                // Virtual dependencies show the package providing them
+                if dep_name(&node.dep) == name {
+                    spans.push(Span::from(node.dep.clone()));
+                } else {
+                    spans.push(Span::from(format!("{} → {name}", node.dep)));
+                }
+
+                if let Some(size) = self.subtree_sizes.get(name) {
+                    spans.push(Span::from(format!(" ({})", to_human_bytes(*size as f64))));
+                }
+
+                if node.cycle {
+                    spans.push(Span::from(" [cycle]").fg(Color::from_u32(colors.text.warning)));
+                }
+            }
+            None => {
+                spans.push(Span::from(node.dep.clone()));
+                spans.push(Span::from(" [missing]").fg(Color::from_u32(colors.text.warning)));
+            }
+        }
+
+        Line::from(spans)
+    }
+}
+
+/// Name of the package in a dependency, without its version constraint.
+fn dep_name(dep: &str) -> &str {
+    dep.split(['<', '>', '=']).next().unwrap_or(dep)
+}
@@ -0,0 +1,163 @@
+use super::transaction::{TransactionLog, stale_upgrades_warning, summary_text};
+use crate::{
+    backend::{TransactionEvent, TransactionPackage},
+    config::Colors,
+    error,
+    utils::create_block,
+};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Stylize},
+    text::Text,
+    widgets::{Paragraph, Widget},
+};
+
+#[derive(Clone, Copy, Default)]
+pub enum UpgradeStates {
+    #[default]
+    Confirmation,
+    Upgrading,
+    Finished(bool),
+}
+
+#[derive(Default)]
+pub struct UpgradeWidget {
+    state: UpgradeStates,
+    packages: Vec<String>,
+    preview: Vec<TransactionPackage>,
+    preview_error: Option<String>,
+    /// Upgrades the system databases do not know yet.
+    stale_upgrades: Vec<String>,
+    transaction_log: TransactionLog,
+}
+
+impl UpgradeWidget {
+    /// Create the popup for an upgrade also installing `packages`.
+    pub fn new(packages: Vec<String>) -> Self {
+        Self {
+            packages,
+            ..Default::default()
+        }
+    }
+
+    pub fn render(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let [msg_area, progress_area, log_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.render_msg_box(msg_area, buf, colors);
+        self.transaction_log
+            .render_progress_bar(progress_area, buf, colors);
+
+        match self.state {
+            UpgradeStates::Confirmation => {
+                let mut text = Text::from(stale_upgrades_warning(&self.stale_upgrades, colors));
+                text.extend(self.preview_text());
+
+                self.transaction_log
+                    .render_lines(log_area, buf, colors, text)
+            }
+            _ => self.transaction_log.render_log(log_area, buf, colors),
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.transaction_log.next();
+    }
+
+    pub fn previous(&mut self) {
+        self.transaction_log.previous();
+    }
+
+    /// Packages installed along the upgrade.
+    pub fn packages(&self) -> &[String] {
+        &self.packages
+    }
+
+    /// Set the packages the upgrade would change, or why it cannot be done.
+    pub fn set_preview(&mut self, preview: error::Result<Vec<TransactionPackage>>) {
+        match preview {
+            Ok(packages) => {
+                self.preview = packages;
+                self.preview_error = None;
+            }
+            Err(err) => {
+                self.preview.clear();
+                self.preview_error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Set the upgrades the system databases do not know yet, to warn about them.
+    pub fn set_stale_upgrades(&mut self, stale_upgrades: Vec<String>) {
+        self.stale_upgrades = stale_upgrades;
+    }
+
+    /// Whether the system databases must be refreshed to get every upgrade of the list.
+    pub fn has_stale_upgrades(&self) -> bool {
+        !self.stale_upgrades.is_empty()
+    }
+
+    /// Whether the upgrade can be confirmed.
+    pub fn can_upgrade(&self) -> bool {
+        matches!(self.state, UpgradeStates::Confirmation)
+            && self.preview_error.is_none()
+            && !self.preview.is_empty()
+    }
+
+    /// Whether a transaction is currently running.
+    pub fn is_upgrading(&self) -> bool {
+        matches!(self.state, UpgradeStates::Upgrading)
+    }
+
+    pub fn start_upgrade(&mut self) {
+        self.state = UpgradeStates::Upgrading;
+        self.transaction_log = Default::default();
+    }
+
+    /// Update the log and progress bar with a transaction event.
+    pub fn handle_event(&mut self, event: TransactionEvent) {
+        if let TransactionEvent::Finished(result) = &event {
+            self.state = UpgradeStates::Finished(result.is_ok());
+        }
+
+        self.transaction_log.handle_event(event);
+    }
+
+    fn preview_text(&self) -> Text<'static> {
+        match &self.preview_error {
+            Some(error) => Text::from(format!("error: {error}")),
+            None if self.preview.is_empty() => Text::from("there is nothing to do"),
+            None => summary_text(&self.preview),
+        }
+    }
+
+    fn render_msg_box(&self, area: Rect, buf: &mut Buffer, colors: &Colors) {
+        let block = create_block(Some(" full system upgrade ".to_string()), None, colors);
+
+        let message = match self.state {
+            UpgradeStates::Confirmation if self.has_stale_upgrades() && self.can_upgrade() => {
+                "Outdated system databases! Refresh [shift+y] | upgrade anyway [Enter] | cancel [ESC]"
+            }
+            UpgradeStates::Confirmation if self.has_stale_upgrades() => {
+                "Outdated system databases! Refresh [shift+y] | cancel [ESC]"
+            }
+            UpgradeStates::Confirmation if self.can_upgrade() => "Upgrade system? [Enter/ESC]",
+            UpgradeStates::Confirmation => "Cannot upgrade system [ESC]",
+            UpgradeStates::Upgrading => "Upgrading",
+            UpgradeStates::Finished(true) => "Upgrade finished [ESC]",
+            UpgradeStates::Finished(false) => "Upgrade failed [ESC]",
+        };
+
+        Paragraph::new(message)
+            .block(block)
+            .centered()
+            .bg(Color::from_u32(colors.ui.background))
+            .fg(Color::from_u32(colors.text.text))
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,631 @@
+use crate::{config::PrivilegeHelper, error, privilege};
+use alpm::vercmp;
+use chrono::{DateTime, Local};
+use nix::unistd::Uid;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::HashSet, fmt, io, path::Path, process::Command};
+use strum_macros::{Display, EnumIter, FromRepr};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Source of packages and transactions the app runs against.
+pub trait PackageBackend {
+    /// Installed packages.
+    fn packages(&self) -> Box<dyn Iterator<Item = PackageData> + '_>;
+
+    /// Every package available in the repositories, installed or not.
+    fn repository_packages(&self) -> Box<dyn Iterator<Item = PackageData> + '_>;
+
+    /// Details of a package, preferring the installed one.
+    fn package(&self, name: &str) -> Option<PackageData>;
+
+    /// Package satisfying the dependency `dep`, e.g. `sh` or `glibc>=2.27`, preferring the
+    /// installed one.
+    fn satisfier(&self, dep: &str) -> Option<PackageData>;
+
+    /// Files of a package, from the local database when installed and from the sync `.files`
+    /// databases otherwise. `None` when no file list is available.
+    fn files(&self, name: &str) -> Option<Vec<PackageFile>>;
+
+    /// Look up the packages owning the file `query` in the background, like `pacman -Qo` for
+    /// installed packages and `pacman -F` for the others. See [`file_matches`].
+    fn spawn_owner_lookup(&self, query: String) -> UnboundedReceiver<Vec<FileOwner>>;
+
+    /// Check the installed files against the mtree data of their packages, like
+    /// `pacman -Qkk`, in the background. Every installed package is checked when `packages`
+    /// is empty.
+    ///
+    /// The returned receiver ends once every package is checked.
+    fn spawn_integrity_check(&self, packages: Vec<String>) -> UnboundedReceiver<IntegrityEvent>;
+
+    /// Backup files of the installed packages with the `.pacnew` and `.pacsave` files left next
+    /// to them, like `pacdiff`.
+    fn backup_files(&self) -> Vec<BackupFile>;
+
+    /// Content of the text file at `path`, relative to the root.
+    fn read_file(&self, path: &str) -> io::Result<String>;
+
+    /// Command merging the `leftover` file into the backup file at `path` interactively with
+    /// `$DIFFPROG`, like `pacdiff`. Paths are relative to the root.
+    fn merge_command(&self, path: &str, leftover: &str) -> error::Result<Command>;
+
+    /// Transactions recorded in the pacman log, oldest first.
+    fn history(&self) -> error::Result<Vec<HistoryTransaction>>;
+
+    /// Pick up changes made by a transaction.
+    fn reload(&mut self) -> error::Result<()>;
+
+    /// Check whether syncing `packages` alone would leave the system partially upgraded.
+    fn partial_upgrade(&self, packages: &HashSet<String>) -> PartialUpgrade;
+
+    /// Upgrades shown in the list that the system sync databases, which transactions run
+    /// against, do not know yet.
+    fn stale_upgrades(&self) -> Vec<String>;
+
+    /// Packages a full system upgrade installing `packages` would install, upgrade or replace.
+    fn system_upgrade_preview(
+        &mut self,
+        packages: &[String],
+    ) -> error::Result<Vec<TransactionPackage>>;
+
+    /// Packages a removal would take away, including cascaded dependencies.
+    fn removal_preview(
+        &mut self,
+        packages: &[String],
+        mode: RemoveMode,
+    ) -> error::Result<Vec<TransactionPackage>>;
+
+    /// Installed dependencies no explicitly installed package needs, directly or not.
+    fn orphans(&self) -> Vec<PackageData> {
+        let packages: Vec<PackageData> = self.packages().collect();
+        let orphans = recursive_orphans(&packages, &HashSet::new());
+
+        packages
+            .into_iter()
+            .filter(|pkg| orphans.contains(&pkg.name))
+            .collect()
+    }
+
+    /// Rights transactions run with.
+    fn privileges(&self) -> Privileges;
+
+    /// Whether `transaction` needs super-user rights.
+    fn requires_root(&self, transaction: &Transaction) -> bool;
+
+    /// Run a transaction in the background.
+    ///
+    /// The returned receiver yields the transaction progress and ends with
+    /// [`TransactionEvent::Finished`].
+    fn spawn_transaction(&self, transaction: Transaction) -> UnboundedReceiver<TransactionEvent>;
+}
+
+/// Package as shown in the app.
+#[derive(Debug, Clone, Default)]
+pub struct PackageData {
+    pub name: String,
+    pub version: String,
+    pub new_version: Option<String>,
+    pub description: Option<String>,
+    pub architecture: Option<String>,
+    pub url: Option<String>,
+    pub repository: Option<String>,
+    pub installed: bool,
+    /// Installed explicitly rather than as a dependency.
+    pub explicit: bool,
+    pub groups: Vec<String>,
+    pub licenses: Vec<String>,
+    pub provides: Vec<String>,
+    pub dependencies: Vec<String>,
+    pub optional_dependencies: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub replaces: Vec<String>,
+    /// Installed packages depending on this one.
+    pub required_by: Vec<String>,
+    /// Installed packages optionally depending on this one.
+    pub optional_for: Vec<String>,
+    /// Installed size in bytes.
+    pub size: i64,
+    pub packager: Option<String>,
+    pub install_date: Option<DateTime<Local>>,
+    /// When the package version was built.
+    pub build_date: Option<DateTime<Local>>,
+}
+
+impl PackageData {
+    /// Installed as a dependency and not even optionally needed by an installed package, like
+    /// `pacman -Qdt`.
+    pub fn is_orphan(&self) -> bool {
+        self.installed
+            && !self.explicit
+            && self.required_by.is_empty()
+            && self.optional_for.is_empty()
+    }
+
+    /// Installed but found in no sync database, like `pacman -Qm`.
+    pub fn is_foreign(&self) -> bool {
+        self.installed && self.repository.is_none()
+    }
+}
+
+/// File owned by a package.
+#[derive(Debug, Clone, Default)]
+pub struct PackageFile {
+    /// Path relative to the root, directories end with `/`.
+    pub path: String,
+    /// Size recorded in the package database.
+    pub size: i64,
+    /// Size of the file on disk, `None` when it is missing.
+    pub disk_size: Option<u64>,
+}
+
+impl PackageFile {
+    pub fn is_dir(&self) -> bool {
+        self.path.ends_with('/')
+    }
+}
+
+/// Package owning a file.
+#[derive(Debug, Clone)]
+pub struct FileOwner {
+    pub package: String,
+    /// Repository the file list comes from, `None` for an installed package.
+    pub repository: Option<String>,
+    /// Path of the file, relative to the root.
+    pub path: String,
+}
+
+/// Whether `path`, relative to the root, is the file `query` refers to.
+///
+/// A query with a `/` is a full path, like `pacman -Qo /usr/bin/bash`. Otherwise it is the
+/// name of a file that is not a directory, like `pacman -F bash`.
+pub fn file_matches(path: &str, query: &str) -> bool {
+    if query.contains('/') {
+        path.trim_end_matches('/') == query.trim_matches('/')
+    } else {
+        !path.ends_with('/') && path.rsplit('/').next() == Some(query)
+    }
+}
+
+/// Resolve the symlinked directories of the absolute path `query` under `root`, like
+/// `pacman -Qo` does, so `/bin/bash` is found as `/usr/bin/bash`. The file itself is not
+/// resolved, as packages own symlinks too.
+pub fn resolve_query(root: &Path, query: &str) -> String {
+    let Some((dir, name)) = query
+        .strip_prefix('/')
+        .and_then(|path| path.trim_end_matches('/').rsplit_once('/'))
+    else {
+        return query.to_string();
+    };
+
+    let resolved = root
+        .canonicalize()
+        .and_then(|root| Ok((root.join(dir).canonicalize()?, root)));
+
+    match resolved {
+        Ok((dir, root)) => match dir.strip_prefix(&root) {
+            Ok(dir) if dir.as_os_str().is_empty() => format!("/{name}"),
+            Ok(dir) => format!("/{}/{name}", dir.display()),
+            Err(_) => query.to_string(),
+        },
+        Err(_) => query.to_string(),
+    }
+}
+
+/// How an installed file differs from what its package recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileIssue {
+    Missing,
+    /// A file replaced by a directory or a symlink, or the other way around.
+    Type,
+    Permissions {
+        recorded: u32,
+        actual: u32,
+    },
+    Size {
+        recorded: u64,
+        actual: u64,
+    },
+    ModificationTime {
+        recorded: i64,
+        actual: i64,
+    },
+    Checksum,
+    LinkTarget {
+        recorded: String,
+        actual: String,
+    },
+    /// The file could not be read to be compared.
+    Unreadable(String),
+}
+
+impl fmt::Display for FileIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing"),
+            Self::Type => write!(f, "type mismatch"),
+            Self::Permissions { recorded, actual } => {
+                write!(f, "permissions mismatch ({recorded:o} → {actual:o})")
+            }
+            Self::Size { recorded, actual } => write!(f, "size mismatch ({recorded} → {actual})"),
+            Self::ModificationTime { recorded, actual } => write!(
+                f,
+                "modification time mismatch ({} → {})",
+                format_timestamp(*recorded),
+                format_timestamp(*actual)
+            ),
+            Self::Checksum => write!(f, "checksum mismatch"),
+            Self::LinkTarget { recorded, actual } => {
+                write!(f, "symlink target mismatch ({recorded} → {actual})")
+            }
+            Self::Unreadable(err) => write!(f, "unreadable: {err}"),
+        }
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|datetime| {
+            datetime
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Result of checking the files of an installed package.
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub package: String,
+    /// Number of files checked.
+    pub checked: usize,
+    /// Paths, relative to the root, of the files differing from the package.
+    pub issues: Vec<(String, FileIssue)>,
+    /// Why the package could not be checked, e.g. its mtree data is missing.
+    pub error: Option<String>,
+}
+
+/// Progress of an integrity check.
+#[derive(Debug, Clone)]
+pub enum IntegrityEvent {
+    /// The check started on this many packages.
+    Started(usize),
+    Checked(IntegrityReport),
+}
+
+/// Configuration file listed in the `backup` array of an installed package.
+#[derive(Debug, Clone)]
+pub struct BackupFile {
+    pub package: String,
+    /// Path relative to the root.
+    pub path: String,
+    pub status: BackupStatus,
+    /// `.pacnew` and `.pacsave` files left next to it, relative to the root.
+    pub leftovers: Vec<String>,
+}
+
+/// How a backup file compares to the version its package installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum BackupStatus {
+    #[strum(to_string = "unmodified")]
+    Unmodified,
+    #[strum(to_string = "modified")]
+    Modified,
+    #[strum(to_string = "missing")]
+    Missing,
+    /// The file could not be read to compute its checksum.
+    #[strum(to_string = "unreadable")]
+    Unreadable,
+}
+
+/// Backup file a `.pacnew`, `.pacsave` or numbered `.pacsave.N` file was left next to.
+pub fn leftover_origin(path: &str) -> Option<&str> {
+    if let Some(origin) = path.strip_suffix(".pacnew") {
+        return Some(origin);
+    }
+
+    let origin = path
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .strip_suffix('.')
+        .unwrap_or(path);
+
+    origin.strip_suffix(".pacsave")
+}
+
+/// Transaction recorded in the pacman log.
+#[derive(Debug, Clone)]
+pub struct HistoryTransaction {
+    /// When the transaction started.
+    pub date: DateTime<Local>,
+    /// Command that ran the transaction, e.g. `pacman -Syu`, when pacman logged it.
+    pub command: Option<String>,
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// Package change recorded in the pacman log.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub date: DateTime<Local>,
+    pub action: HistoryAction,
+    pub package: String,
+    /// Version before the change, `None` for an installation.
+    pub old_version: Option<String>,
+    /// Version after the change, `None` for a removal.
+    pub new_version: Option<String>,
+}
+
+/// Change made to a package, as logged by alpm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum HistoryAction {
+    #[strum(to_string = "installed")]
+    Installed,
+    #[strum(to_string = "upgraded")]
+    Upgraded,
+    #[strum(to_string = "downgraded")]
+    Downgraded,
+    #[strum(to_string = "reinstalled")]
+    Reinstalled,
+    #[strum(to_string = "removed")]
+    Removed,
+}
+
+/// Package affected by a transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionPackage {
+    pub name: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    /// Installed size before the transaction.
+    pub old_size: i64,
+    /// Installed size after the transaction.
+    pub new_size: i64,
+    /// Size to download, zero when the package is cached or removed.
+    pub download_size: i64,
+}
+
+/// Signs that syncing a selection would leave the system partially upgraded.
+#[derive(Debug, Default, Clone)]
+pub struct PartialUpgrade {
+    /// Upgradable packages left out of a selection containing other upgrades.
+    pub skipped_upgrades: Vec<String>,
+    /// Outdated installed packages that selected packages depend on, with their dependent.
+    pub outdated_dependencies: Vec<(String, String)>,
+}
+
+impl PartialUpgrade {
+    /// Whether the selection would result in a partial upgrade.
+    pub fn is_partial(&self) -> bool {
+        !self.skipped_upgrades.is_empty() || !self.outdated_dependencies.is_empty()
+    }
+}
+
+/// How packages are removed, mirroring pacman's `-R`, `-Rs` and `-Rns`.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Display, FromRepr, EnumIter, Serialize, Deserialize,
+)]
+pub enum RemoveMode {
+    #[default]
+    #[strum(to_string = "-R")]
+    Packages,
+    #[strum(to_string = "-Rs")]
+    Recursive,
+    #[strum(to_string = "-Rns")]
+    RecursiveNoSave,
+}
+
+impl RemoveMode {
+    /// Get previous mode.
+    pub fn previous(self) -> Self {
+        let current_index = self as usize;
+        let previous_index = current_index.saturating_sub(1);
+
+        Self::from_repr(previous_index).unwrap_or(self)
+    }
+
+    /// Get next mode.
+    pub fn next(self) -> Self {
+        let current_index = self as usize;
+        let next_index = current_index.saturating_add(1);
+
+        Self::from_repr(next_index).unwrap_or(self)
+    }
+
+    /// Describe what the mode removes.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Packages => "remove packages",
+            Self::Recursive => "remove packages and unneeded dependencies",
+            Self::RecursiveNoSave => "remove packages, unneeded dependencies and config backups",
+        }
+    }
+}
+
+/// Transaction run in the background by [`PackageBackend::spawn_transaction`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Transaction {
+    /// Refresh the package databases.
+    Refresh,
+    /// Refresh the system sync databases, like `pacman -Sy`, whatever the refresh mode.
+    RefreshSystem,
+    Sync(Vec<String>),
+    Remove(Vec<String>, RemoveMode),
+    SystemUpgrade(Vec<String>),
+    /// Mark packages as explicitly installed and remove orphans.
+    CleanOrphans {
+        remove: Vec<String>,
+        mark_explicit: Vec<String>,
+    },
+    /// Move a `.pacnew` or `.pacsave` file over the backup file it was left next to.
+    ReplaceBackup(String),
+    /// Delete a `.pacnew` or `.pacsave` file.
+    DeleteLeftover(String),
+}
+
+/// Progress reported while a transaction runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TransactionEvent {
+    /// Informational line, e.g. a transaction step or hook output.
+    Log(String),
+    /// Download progress of a single file.
+    Download {
+        file: String,
+        downloaded: i64,
+        total: i64,
+    },
+    /// Progress of an operation on a package.
+    Progress {
+        operation: String,
+        package: String,
+        percent: i32,
+        current: usize,
+        total: usize,
+    },
+    /// Transaction finished with the affected packages or an error message.
+    Finished(Result<Vec<TransactionPackage>, String>),
+}
+
+/// Rights transactions run with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Privileges {
+    /// Running as root.
+    Root,
+    /// Escalating through a helper for each transaction.
+    Escalate(PrivilegeHelper),
+    /// The helper is not installed, only browsing is possible.
+    ReadOnly(PrivilegeHelper),
+}
+
+impl Privileges {
+    /// Rights of the current process, without escalation.
+    pub fn current() -> Self {
+        Self::with_helper(PrivilegeHelper::None)
+    }
+
+    /// Rights of the current process, escalating through `helper` when not root.
+    pub fn with_helper(helper: PrivilegeHelper) -> Self {
+        if Uid::effective().is_root() {
+            Self::Root
+        } else if helper != PrivilegeHelper::None && privilege::is_installed(helper) {
+            Self::Escalate(helper)
+        } else {
+            Self::ReadOnly(helper)
+        }
+    }
+
+    /// Why transactions cannot run, in read-only mode.
+    pub fn read_only_reason(self) -> Option<String> {
+        match self {
+            Self::ReadOnly(PrivilegeHelper::None) => {
+                Some("run Tecarius as root for transactions".to_string())
+            }
+            Self::ReadOnly(helper) => Some(format!("{helper} is not installed")),
+            _ => None,
+        }
+    }
+}
+
+/// Orphans among the installed `packages`, including the dependencies only orphans need,
+/// like running `pacman -Qdtq` until no orphan is left. Packages in `keep` are never orphans.
+pub fn recursive_orphans(packages: &[PackageData], keep: &HashSet<String>) -> HashSet<String> {
+    let mut orphans = HashSet::new();
+
+    loop {
+        let new_orphans: Vec<&PackageData> = packages
+            .iter()
+            .filter(|pkg| {
+                pkg.installed
+                    && !pkg.explicit
+                    && !keep.contains(&pkg.name)
+                    && !orphans.contains(&pkg.name)
+                    && pkg
+                        .required_by
+                        .iter()
+                        .chain(&pkg.optional_for)
+                        .all(|dependent| orphans.contains(dependent))
+            })
+            .collect();
+
+        if new_orphans.is_empty() {
+            return orphans;
+        }
+
+        orphans.extend(new_orphans.into_iter().map(|pkg| pkg.name.clone()));
+    }
+}
+
+/// Whether `version` is newer than `current`.
+pub fn is_newer(version: &str, current: &str) -> bool {
+    vercmp(current, version) == Ordering::Less
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &str, required_by: &[&str], optional_for: &[&str]) -> PackageData {
+        PackageData {
+            name: name.to_string(),
+            installed: true,
+            required_by: required_by.iter().map(|name| name.to_string()).collect(),
+            optional_for: optional_for.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn orphans_include_dependencies_only_orphans_need() {
+        let packages = vec![
+            PackageData {
+                explicit: true,
+                ..dependency("bash", &[], &[])
+            },
+            dependency("readline", &["bash"], &[]),
+            dependency("python", &[], &[]),
+            dependency("expat", &["python"], &[]),
+            dependency("mpdecimal", &["python"], &["bash"]),
+            dependency("tk", &[], &["python"]),
+            dependency("kept", &[], &[]),
+            PackageData {
+                installed: false,
+                ..dependency("repository-only", &[], &[])
+            },
+        ];
+        let keep = HashSet::from(["kept".to_string()]);
+
+        assert_eq!(
+            recursive_orphans(&packages, &keep),
+            HashSet::from(["python", "expat", "tk"].map(String::from))
+        );
+        assert!(packages[2].is_orphan());
+        assert!(!packages[3].is_orphan());
+        assert!(!packages[5].is_orphan());
+    }
+
+    #[test]
+    fn leftovers_point_to_their_backup_file() {
+        assert_eq!(
+            leftover_origin("etc/pacman.conf.pacnew"),
+            Some("etc/pacman.conf")
+        );
+        assert_eq!(leftover_origin("etc/hosts.pacsave"), Some("etc/hosts"));
+        assert_eq!(leftover_origin("etc/hosts.pacsave.1"), Some("etc/hosts"));
+        assert_eq!(leftover_origin("etc/hosts.pacsave.12"), Some("etc/hosts"));
+        assert_eq!(leftover_origin("etc/hosts"), None);
+        assert_eq!(leftover_origin("etc/hosts.1"), None);
+        assert_eq!(leftover_origin("etc/hosts.pacnew.1"), None);
+    }
+
+    #[test]
+    fn symlinked_directories_are_resolved() {
+        let root = std::env::temp_dir().join(format!("tecarius-root-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("usr/bin")).unwrap();
+        _ = std::os::unix::fs::symlink("usr/bin", root.join("bin"));
+
+        let bash = resolve_query(&root, "/bin/bash");
+        let unknown = resolve_query(&root, "/nowhere/bash");
+        _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(bash, "/usr/bin/bash");
+        assert_eq!(unknown, "/nowhere/bash");
+        assert_eq!(resolve_query(Path::new("/"), "bash"), "bash");
+    }
+}
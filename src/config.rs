@@ -1,18 +1,182 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
 use cross_xdg::BaseDirs;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{env, fs, path::PathBuf};
+use strum_macros::{Display, FromRepr};
 
 /// User configuration.
 #[derive(Deserialize)]
+#[serde(default)]
 pub struct UserConfig {
-    theme: String,
+    pub theme: String,
+    /// Refresh the package databases when starting.
+    pub refresh_on_startup: bool,
+    pub refresh_mode: RefreshMode,
+    /// Command escalating privileges for transactions when not running as root.
+    pub privilege_helper: PrivilegeHelper,
+    /// Order of fuzzy search results with the same score, the list order when unset.
+    pub search_tie_breaker: Option<TieBreaker>,
+    /// Order of the package list on startup. When unset, the order last chosen in the app is
+    /// restored instead.
+    pub sort: Option<SortOrder>,
+    pub table: TableConfig,
 }
 
 impl Default for UserConfig {
     fn default() -> Self {
         Self {
             theme: "catppuccin-mocha".to_string(),
+            refresh_on_startup: false,
+            refresh_mode: Default::default(),
+            privilege_helper: Default::default(),
+            search_tie_breaker: Default::default(),
+            sort: Default::default(),
+            table: Default::default(),
+        }
+    }
+}
+
+/// How the package databases are refreshed.
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefreshMode {
+    /// Refresh the system sync databases, like `pacman -Sy`.
+    #[default]
+    Sync,
+    /// Refresh a temporary copy of the sync databases, like `checkupdates`, leaving the
+    /// system ones untouched. Transactions still run against the system databases.
+    CheckUpdates,
+}
+
+/// Command used to run transactions as root.
+#[derive(Deserialize, Display, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum PrivilegeHelper {
+    #[default]
+    Sudo,
+    Doas,
+    Pkexec,
+    Run0,
+    /// Never escalate, transactions need Tecarius to run as root.
+    None,
+}
+
+/// Order of search results matching equally well.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TieBreaker {
+    /// Shorter names first, so `firefox` comes before `firefox-i18n-fr`.
+    Length,
+    /// Alphabetical order.
+    Name,
+    /// Bigger packages first.
+    Size,
+    /// Order of the package list.
+    DbOrder,
+}
+
+/// Order of the package list.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+/// Package field the list is sorted by.
+#[derive(Serialize, Deserialize, Display, FromRepr, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "lowercase")]
+pub enum SortKey {
+    /// Order of the package database.
+    #[default]
+    #[strum(to_string = "db order")]
+    Database,
+    Name,
+    /// Installed size.
+    Size,
+    #[strum(to_string = "install date")]
+    InstallDate,
+    /// Build date of the package version.
+    #[strum(to_string = "build date")]
+    BuildDate,
+    Repository,
+    /// Upgradable packages first.
+    #[strum(to_string = "upgrades")]
+    UpgradesFirst,
+}
+
+impl SortKey {
+    /// Get next key, wrapping around.
+    pub fn next(self) -> Self {
+        Self::from_repr(self as usize + 1).unwrap_or_default()
+    }
+}
+
+/// Package list shown as a table.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TableConfig {
+    /// Start with the table instead of the list of names.
+    pub enabled: bool,
+    /// Columns shown, in order.
+    pub columns: Vec<ColumnConfig>,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        let column = |column, width| ColumnConfig { column, width };
+
+        Self {
+            enabled: false,
+            columns: vec![
+                column(Column::Name, None),
+                column(Column::Version, Some(16)),
+                column(Column::NewVersion, Some(16)),
+                column(Column::Size, Some(10)),
+                column(Column::Repository, Some(10)),
+                column(Column::InstallDate, Some(12)),
+            ],
+        }
+    }
+}
+
+/// Column of the package table.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ColumnConfig {
+    pub column: Column,
+    /// Width in cells, the column shares the remaining space when unset.
+    #[serde(default)]
+    pub width: Option<u16>,
+}
+
+/// Package field shown in a table column.
+#[derive(Deserialize, Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "lowercase")]
+pub enum Column {
+    Name,
+    Version,
+    #[strum(to_string = "new version")]
+    NewVersion,
+    Size,
+    #[strum(to_string = "repo")]
+    Repository,
+    #[strum(to_string = "installed")]
+    InstallDate,
+}
+
+impl Column {
+    /// Sort key ordering the list by this column.
+    pub fn sort_key(self) -> Option<SortKey> {
+        match self {
+            Self::Name => Some(SortKey::Name),
+            Self::Version => None,
+            Self::NewVersion => Some(SortKey::UpgradesFirst),
+            Self::Size => Some(SortKey::Size),
+            Self::Repository => Some(SortKey::Repository),
+            Self::InstallDate => Some(SortKey::InstallDate),
         }
     }
 }
@@ -38,6 +202,7 @@ pub struct UI {
 pub struct Text {
     pub title: u32,
     pub text: u32,
+    pub warning: u32,
 }
 
 /// Input colors.
@@ -58,6 +223,7 @@ impl Default for Colors {
             text: Text {
                 title: 0xa6e3a1,
                 text: 0xf5e0dc,
+                warning: 0xf38ba8,
             },
             input: Input {
                 typing: 0x94e2d4,
@@ -67,13 +233,46 @@ impl Default for Colors {
     }
 }
 
-/// Get the color configuration.
-pub fn theme_colors() -> Result<Colors> {
+/// Get the user configuration.
+pub fn user_config() -> Result<UserConfig> {
     let user_config_path = BaseDirs::new()?.config_home().join("tecarius/config.toml");
 
     let user_config = fs::read_to_string(user_config_path)?;
     let user_config: UserConfig = toml::from_str(&user_config)?;
 
+    Ok(user_config)
+}
+
+/// Get the list order last chosen in the app, if any.
+pub fn saved_sort_order() -> Result<SortOrder> {
+    let sort = fs::read_to_string(sort_order_path()?)?;
+    let sort: SortOrder = toml::from_str(&sort)?;
+
+    Ok(sort)
+}
+
+/// Save the list order in a state file, leaving the user configuration untouched.
+pub fn save_sort_order(sort: SortOrder) -> Result<()> {
+    let save = || -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let sort_order_path = sort_order_path()?;
+
+        if let Some(parent) = sort_order_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(sort_order_path, toml::to_string(&sort)?)?;
+
+        Ok(())
+    };
+
+    save().map_err(|err| Error::SaveSortOrderError(err.to_string()))
+}
+
+fn sort_order_path() -> Result<PathBuf> {
+    Ok(BaseDirs::new()?.state_home().join("tecarius/sort.toml"))
+}
+
+/// Get the color configuration of the user theme.
+pub fn theme_colors(user_config: &UserConfig) -> Result<Colors> {
     let theme_path = &PathBuf::from(env::var("CARGO_MANIFEST_DIR")?)
         .join(format!("themes/{}.toml", user_config.theme));
 
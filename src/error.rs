@@ -22,12 +22,57 @@ pub enum Error {
     #[error("Failed loading user configuration.")]
     UserConfigurationError(#[from] de::Error),
 
+    #[error("Failed saving the sort order: {0}.")]
+    SaveSortOrderError(String),
+
     #[error("Failed loading pacman.conf.")]
     PacmanConfError(#[from] pacmanconf::Error),
 
+    #[error("Failed loading fixture: {0}.")]
+    FixtureError(String),
+
+    #[error("Transaction failed: {0}.")]
+    TransactionError(alpm::Error),
+
+    #[error("Failed to refresh package databases: {0}.")]
+    RefreshError(alpm::Error),
+
+    #[error("Package {0} was not found in the sync databases.")]
+    PackageNotFound(String),
+
+    #[error("Package {0} is not installed.")]
+    PackageNotInstalled(String),
+
+    #[error("Unsatisfied dependencies: {}.", .0.join(", "))]
+    UnsatisfiedDependencies(Vec<String>),
+
+    #[error("Conflicting packages: {}.", .0.join(", "))]
+    ConflictingPackages(Vec<String>),
+
+    #[error("Packages with invalid architecture: {}.", .0.join(", "))]
+    InvalidArchitecture(Vec<String>),
+
+    #[error("Conflicting files: {}.", .0.join(", "))]
+    FileConflicts(Vec<String>),
+
+    #[error("Invalid or corrupted packages: {}.", .0.join(", "))]
+    InvalidPackages(Vec<String>),
+
     #[error("Failed to access super-user rights.")]
     SuperUserError,
 
+    #[error("Privilege helper failed: {0}.")]
+    HelperError(String),
+
+    #[error("Merging is not available: {0}.")]
+    MergeUnavailable(String),
+
+    #[error("Failed to resolve {0}: {1}.")]
+    LeftoverError(String, String),
+
+    #[error("Failed to read the pacman log: {0}.")]
+    HistoryError(String),
+
     #[error("Failed to send event between tasks")]
     EventSendError(#[from] error::SendError<KeyboardEvent>),
 
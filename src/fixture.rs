@@ -0,0 +1,1117 @@
+use crate::{
+    backend::{
+        BackupFile, BackupStatus, FileIssue, FileOwner, HistoryTransaction, IntegrityEvent,
+        IntegrityReport, PackageBackend, PackageData, PackageFile, PartialUpgrade, Privileges,
+        RemoveMode, Transaction, TransactionEvent, TransactionPackage, file_matches, is_newer,
+        leftover_origin,
+    },
+    error::{self, Error},
+    history,
+};
+use alpm::vercmp;
+use chrono::{DateTime, Local, TimeZone};
+use serde::Deserialize;
+use std::{
+    collections::{HashSet, VecDeque},
+    fs, io, iter,
+    path::Path,
+    process::Command,
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+    time::Duration,
+};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// Delay between the steps of a simulated transaction, so its progress can be followed.
+const STEP_DELAY: Duration = Duration::from_millis(150);
+
+/// Size reported for each repository database downloaded by a simulated refresh.
+const REPOSITORY_DB_SIZE: i64 = 128 * 1024;
+
+/// In-memory package database loaded from a TOML or JSON fixture file.
+///
+/// Transactions are simulated on the in-memory database, nothing is written back to the file.
+pub struct FixtureBackend {
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+/// Fixture file layout.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+struct Fixture {
+    installed: Vec<FixturePackage>,
+    repositories: Vec<FixtureRepository>,
+    /// Content of the pacman log, extended by simulated transactions.
+    log: String,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+struct FixtureRepository {
+    name: String,
+    packages: Vec<FixturePackage>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+struct FixturePackage {
+    name: String,
+    version: String,
+    description: Option<String>,
+    architecture: Option<String>,
+    url: Option<String>,
+    licenses: Vec<String>,
+    provides: Vec<String>,
+    depends: Vec<String>,
+    optdepends: Vec<String>,
+    conflicts: Vec<String>,
+    replaces: Vec<String>,
+    groups: Vec<String>,
+    /// Installed size in bytes.
+    size: i64,
+    download_size: i64,
+    packager: Option<String>,
+    /// Unix timestamp of the installation.
+    install_date: Option<i64>,
+    /// Unix timestamp of the build.
+    build_date: Option<i64>,
+    /// Installed as a dependency of another package.
+    dependency: bool,
+    files: Vec<FixtureFile>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+struct FixtureFile {
+    /// Path relative to the root, directories end with `/`.
+    path: String,
+    size: i64,
+    /// Deleted from disk since the package was installed.
+    missing: bool,
+    /// Content changed since the package was installed.
+    modified: bool,
+    /// Listed in the `backup` array of the package.
+    backup: bool,
+    /// Content on disk, shown when comparing a backup file with its leftovers.
+    content: Option<String>,
+    /// Content of the `.pacnew` file left next to it.
+    pacnew: Option<String>,
+    /// Content of the `.pacsave` file left next to it.
+    pacsave: Option<String>,
+}
+
+/// Changes a transaction makes to the fixture.
+#[derive(Default)]
+struct Plan {
+    install: Vec<FixturePackage>,
+    remove: Vec<String>,
+    mark_explicit: Vec<String>,
+}
+
+impl FixtureBackend {
+    /// Load a fixture, parsed as JSON when the file has a `.json` extension and TOML otherwise.
+    pub fn load(path: impl AsRef<Path>) -> error::Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        let fixture: Fixture = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&content).map_err(|err| Error::FixtureError(err.to_string()))?
+        } else {
+            toml::from_str(&content).map_err(|err| Error::FixtureError(err.to_string()))?
+        };
+
+        Ok(Self {
+            fixture: Arc::new(Mutex::new(fixture)),
+        })
+    }
+
+    /// Packages owning the file `query`.
+    fn owners(&self, query: &str) -> Vec<FileOwner> {
+        let fixture = self.fixture();
+        let sync_packages = fixture.repositories.iter().flat_map(|repo| {
+            repo.packages
+                .iter()
+                .filter(|pkg| fixture.installed(&pkg.name).is_none())
+                .map(|pkg| (Some(repo.name.as_str()), pkg))
+        });
+
+        fixture
+            .installed
+            .iter()
+            .map(|pkg| (None, pkg))
+            .chain(sync_packages)
+            .flat_map(|(repository, pkg)| {
+                pkg.files
+                    .iter()
+                    .filter(|file| file_matches(&file.path, query))
+                    .map(move |file| FileOwner {
+                        package: pkg.name.clone(),
+                        repository: repository.map(String::from),
+                        path: file.path.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    fn fixture(&self) -> MutexGuard<'_, Fixture> {
+        self.fixture.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl PackageBackend for FixtureBackend {
+    fn packages(&self) -> Box<dyn Iterator<Item = PackageData> + '_> {
+        let fixture = self.fixture();
+        let packages: Vec<PackageData> = fixture
+            .installed
+            .iter()
+            .map(|pkg| fixture.local_package_data(pkg))
+            .collect();
+
+        Box::new(packages.into_iter())
+    }
+
+    fn repository_packages(&self) -> Box<dyn Iterator<Item = PackageData> + '_> {
+        let fixture = self.fixture();
+        let packages: Vec<PackageData> = fixture
+            .repositories
+            .iter()
+            .flat_map(|repo| {
+                repo.packages
+                    .iter()
+                    .map(|pkg| fixture.sync_package_data(&repo.name, pkg))
+            })
+            .collect();
+
+        Box::new(packages.into_iter())
+    }
+
+    fn package(&self, name: &str) -> Option<PackageData> {
+        let fixture = self.fixture();
+
+        match fixture.installed(name) {
+            Some(pkg) => Some(fixture.local_package_data(pkg)),
+            None => fixture
+                .sync(name)
+                .map(|(repo, pkg)| fixture.sync_package_data(repo, pkg)),
+        }
+    }
+
+    /// Version constraints are ignored.
+    fn satisfier(&self, dep: &str) -> Option<PackageData> {
+        let fixture = self.fixture();
+
+        match fixture.local_satisfier(dep) {
+            Some(pkg) => Some(fixture.local_package_data(pkg)),
+            None => fixture
+                .sync(dep_name(dep))
+                .map(|(repo, pkg)| fixture.sync_package_data(repo, pkg)),
+        }
+    }
+
+    /// Sync packages without files in the fixture have no file list, like a repository
+    /// without a `.files` database.
+    fn files(&self, name: &str) -> Option<Vec<PackageFile>> {
+        let fixture = self.fixture();
+
+        match fixture.installed(name) {
+            Some(pkg) => Some(pkg.files.iter().map(FixtureFile::installed).collect()),
+            None => fixture
+                .repositories
+                .iter()
+                .flat_map(|repo| &repo.packages)
+                .find(|pkg| pkg.name == name && !pkg.files.is_empty())
+                .map(|pkg| pkg.files.iter().map(FixtureFile::available).collect()),
+        }
+    }
+
+    /// The fixture files are not on disk, so symlinked directories are not resolved.
+    fn spawn_owner_lookup(&self, query: String) -> UnboundedReceiver<Vec<FileOwner>> {
+        let (sender, receiver) = unbounded_channel();
+        _ = sender.send(self.owners(&query));
+
+        receiver
+    }
+
+    /// Files flagged missing or modified in the fixture are reported.
+    fn spawn_integrity_check(&self, packages: Vec<String>) -> UnboundedReceiver<IntegrityEvent> {
+        let (sender, receiver) = unbounded_channel();
+        let reports: Vec<IntegrityReport> = self
+            .fixture()
+            .installed
+            .iter()
+            .filter(|pkg| packages.is_empty() || packages.contains(&pkg.name))
+            .map(FixturePackage::integrity_report)
+            .collect();
+
+        thread::spawn(move || {
+            _ = sender.send(IntegrityEvent::Started(reports.len()));
+
+            for report in reports {
+                thread::sleep(STEP_DELAY);
+
+                if sender.send(IntegrityEvent::Checked(report)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        receiver
+    }
+
+    /// Backup files are the files flagged `backup`, with their `modified` and `missing` flags.
+    fn backup_files(&self) -> Vec<BackupFile> {
+        self.fixture()
+            .installed
+            .iter()
+            .flat_map(|pkg| {
+                pkg.files
+                    .iter()
+                    .filter(|file| file.backup)
+                    .map(|file| file.backup_file(&pkg.name))
+            })
+            .collect()
+    }
+
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        self.fixture()
+            .installed
+            .iter()
+            .flat_map(|pkg| &pkg.files)
+            .find_map(|file| file.read(path))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn merge_command(&self, _path: &str, _leftover: &str) -> error::Result<Command> {
+        Err(Error::MergeUnavailable(
+            "merges are not simulated".to_string(),
+        ))
+    }
+
+    fn history(&self) -> error::Result<Vec<HistoryTransaction>> {
+        Ok(history::parse(&self.fixture().log))
+    }
+
+    /// Transactions already change the shared in-memory database, there is nothing to reload.
+    fn reload(&mut self) -> error::Result<()> {
+        Ok(())
+    }
+
+    fn partial_upgrade(&self, packages: &HashSet<String>) -> PartialUpgrade {
+        let fixture = self.fixture();
+        let upgradables: Vec<&str> = fixture
+            .installed
+            .iter()
+            .filter(|pkg| fixture.new_version(pkg).is_some())
+            .map(|pkg| pkg.name.as_str())
+            .collect();
+
+        let mut partial_upgrade = PartialUpgrade::default();
+
+        if upgradables.iter().any(|name| packages.contains(*name)) {
+            partial_upgrade.skipped_upgrades = upgradables
+                .iter()
+                .filter(|name| !packages.contains(**name))
+                .map(|name| name.to_string())
+                .collect();
+        }
+
+        for name in packages {
+            let Some((_, sync_pkg)) = fixture.sync(name) else {
+                continue;
+            };
+
+            for dep in &sync_pkg.depends {
+                let Some(local_pkg) = fixture.local_satisfier(dep) else {
+                    continue;
+                };
+
+                let outdated = upgradables.contains(&local_pkg.name.as_str())
+                    || !local_pkg.satisfies_version(dep);
+
+                if outdated && !packages.contains(&local_pkg.name) {
+                    partial_upgrade
+                        .outdated_dependencies
+                        .push((local_pkg.name.clone(), name.clone()));
+                }
+            }
+        }
+
+        partial_upgrade
+    }
+
+    /// The fixture has a single set of sync databases.
+    fn stale_upgrades(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn system_upgrade_preview(
+        &mut self,
+        packages: &[String],
+    ) -> error::Result<Vec<TransactionPackage>> {
+        let fixture = self.fixture();
+        let plan = fixture.plan(&Transaction::SystemUpgrade(packages.to_vec()))?;
+
+        Ok(fixture.transaction_packages(&plan))
+    }
+
+    fn removal_preview(
+        &mut self,
+        packages: &[String],
+        mode: RemoveMode,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        let fixture = self.fixture();
+        let plan = fixture.plan(&Transaction::Remove(packages.to_vec(), mode))?;
+
+        Ok(fixture.transaction_packages(&plan))
+    }
+
+    /// Simulated transactions never touch the system.
+    fn privileges(&self) -> Privileges {
+        Privileges::Root
+    }
+
+    fn requires_root(&self, _transaction: &Transaction) -> bool {
+        false
+    }
+
+    /// The transaction is simulated on its own thread, step by step.
+    fn spawn_transaction(&self, transaction: Transaction) -> UnboundedReceiver<TransactionEvent> {
+        let (sender, receiver) = unbounded_channel();
+        let fixture = Arc::clone(&self.fixture);
+
+        thread::spawn(move || {
+            let result = simulate_transaction(&fixture, &transaction, &sender);
+
+            _ = sender.send(TransactionEvent::Finished(
+                result.map_err(|err| err.to_string()),
+            ));
+        });
+
+        receiver
+    }
+}
+
+impl Fixture {
+    fn installed(&self, name: &str) -> Option<&FixturePackage> {
+        self.installed.iter().find(|pkg| pkg.name == name)
+    }
+
+    /// Sync package named `name`, or else providing it, with its repository.
+    fn sync(&self, name: &str) -> Option<(&str, &FixturePackage)> {
+        let sync_packages = || {
+            self.repositories.iter().flat_map(|repo| {
+                repo.packages
+                    .iter()
+                    .map(move |pkg| (repo.name.as_str(), pkg))
+            })
+        };
+
+        sync_packages()
+            .find(|(_, pkg)| pkg.name == name)
+            .or_else(|| sync_packages().find(|(_, pkg)| pkg.satisfies(name)))
+    }
+
+    /// Installed package satisfying `dep`, version constraints aside.
+    fn local_satisfier(&self, dep: &str) -> Option<&FixturePackage> {
+        self.installed.iter().find(|pkg| pkg.satisfies(dep))
+    }
+
+    fn new_version(&self, pkg: &FixturePackage) -> Option<&str> {
+        self.repositories
+            .iter()
+            .flat_map(|repo| repo.packages.iter())
+            .find(|sync_pkg| sync_pkg.name == pkg.name)
+            .map(|sync_pkg| sync_pkg.version.as_str())
+            .filter(|version| is_newer(version, &pkg.version))
+    }
+
+    /// Installed packages depending on `pkg`, required or optionally.
+    fn dependents(&self, pkg: &FixturePackage) -> (Vec<String>, Vec<String>) {
+        let dependents = |deps: fn(&FixturePackage) -> &Vec<String>| {
+            self.installed
+                .iter()
+                .filter(|dependent| deps(dependent).iter().any(|dep| pkg.satisfies(dep)))
+                .map(|dependent| dependent.name.clone())
+                .collect()
+        };
+
+        (
+            dependents(|dependent| &dependent.depends),
+            dependents(|dependent| &dependent.optdepends),
+        )
+    }
+
+    fn local_package_data(&self, pkg: &FixturePackage) -> PackageData {
+        let (required_by, optional_for) = self.dependents(pkg);
+
+        PackageData {
+            new_version: self.new_version(pkg).map(String::from),
+            repository: self
+                .repositories
+                .iter()
+                .find(|repo| {
+                    repo.packages
+                        .iter()
+                        .any(|sync_pkg| sync_pkg.name == pkg.name)
+                })
+                .map(|repo| repo.name.clone()),
+            installed: true,
+            explicit: !pkg.dependency,
+            required_by,
+            optional_for,
+            ..pkg.package_data()
+        }
+    }
+
+    fn sync_package_data(&self, repository: &str, pkg: &FixturePackage) -> PackageData {
+        let local_pkg = self.installed(&pkg.name);
+        let (required_by, optional_for) = local_pkg
+            .map(|local_pkg| self.dependents(local_pkg))
+            .unwrap_or_default();
+
+        PackageData {
+            version: local_pkg
+                .map_or(&pkg.version, |local_pkg| &local_pkg.version)
+                .clone(),
+            new_version: local_pkg
+                .filter(|local_pkg| is_newer(&pkg.version, &local_pkg.version))
+                .map(|_| pkg.version.clone()),
+            repository: Some(repository.to_string()),
+            installed: local_pkg.is_some(),
+            explicit: local_pkg.is_some_and(|local_pkg| !local_pkg.dependency),
+            required_by,
+            optional_for,
+            install_date: local_pkg.and_then(FixturePackage::install_date),
+            ..pkg.package_data()
+        }
+    }
+
+    /// Resolve the packages a transaction installs and removes, like alpm would.
+    fn plan(&self, transaction: &Transaction) -> error::Result<Plan> {
+        match transaction {
+            Transaction::Refresh | Transaction::RefreshSystem => Ok(Plan::default()),
+            Transaction::Sync(packages) => self.plan_sync(packages, false),
+            Transaction::SystemUpgrade(packages) => self.plan_sync(packages, true),
+            Transaction::Remove(packages, mode) => self.plan_remove(packages, *mode),
+            Transaction::CleanOrphans {
+                remove,
+                mark_explicit,
+            } => Ok(Plan {
+                mark_explicit: mark_explicit.clone(),
+                ..self.plan_remove(remove, RemoveMode::Packages)?
+            }),
+            Transaction::ReplaceBackup(_) | Transaction::DeleteLeftover(_) => Ok(Plan::default()),
+        }
+    }
+
+    fn plan_sync(&self, targets: &[String], upgrade: bool) -> error::Result<Plan> {
+        let mut queue: VecDeque<String> = targets.iter().cloned().collect();
+        if upgrade {
+            queue.extend(
+                self.installed
+                    .iter()
+                    .filter(|pkg| self.new_version(pkg).is_some())
+                    .map(|pkg| pkg.name.clone()),
+            );
+        }
+
+        let mut plan = Plan::default();
+        let mut missing = Vec::new();
+
+        while let Some(name) = queue.pop_front() {
+            let (_, pkg) = self
+                .sync(&name)
+                .ok_or_else(|| Error::PackageNotFound(name.clone()))?;
+            let local_pkg = self.installed(&pkg.name);
+
+            // Already planned, or up to date like with `--needed`
+            if plan.install.iter().any(|planned| planned.name == pkg.name)
+                || local_pkg.is_some_and(|local_pkg| local_pkg.version == pkg.version)
+            {
+                continue;
+            }
+
+            for dep in &pkg.depends {
+                let satisfied = self.local_satisfier(dep).is_some()
+                    || plan.install.iter().any(|planned| planned.satisfies(dep))
+                    || queue.iter().any(|queued| queued == dep_name(dep));
+
+                if satisfied {
+                    continue;
+                }
+
+                if self.sync(dep_name(dep)).is_some() {
+                    queue.push_back(dep_name(dep).to_string());
+                } else {
+                    missing.push(format!("{} requires {}", pkg.name, dep));
+                }
+            }
+
+            plan.install.push(FixturePackage {
+                dependency: local_pkg.map_or(!targets.contains(&pkg.name), |local_pkg| {
+                    local_pkg.dependency
+                }),
+                ..pkg.clone()
+            });
+        }
+
+        if missing.is_empty() {
+            Ok(plan)
+        } else {
+            Err(Error::UnsatisfiedDependencies(missing))
+        }
+    }
+
+    fn plan_remove(&self, targets: &[String], mode: RemoveMode) -> error::Result<Plan> {
+        let mut removed: Vec<&FixturePackage> = targets
+            .iter()
+            .map(|name| {
+                self.installed(name)
+                    .ok_or_else(|| Error::PackageNotInstalled(name.clone()))
+            })
+            .collect::<error::Result<_>>()?;
+
+        let is_removed =
+            |removed: &[&FixturePackage], name: &str| removed.iter().any(|pkg| pkg.name == name);
+        let is_required = |removed: &[&FixturePackage], pkg: &FixturePackage| {
+            self.installed.iter().any(|other| {
+                !is_removed(removed, &other.name)
+                    && other.depends.iter().any(|dep| pkg.satisfies(dep))
+            })
+        };
+
+        // Cascade to dependencies nothing else requires, until nothing changes
+        if mode != RemoveMode::Packages {
+            while let Some(pkg) = self.installed.iter().find(|pkg| {
+                pkg.dependency
+                    && !is_removed(&removed, &pkg.name)
+                    && removed
+                        .iter()
+                        .any(|target| target.depends.iter().any(|dep| pkg.satisfies(dep)))
+                    && !is_required(&removed, pkg)
+            }) {
+                removed.push(pkg);
+            }
+        }
+
+        let missing: Vec<String> =
+            self.installed
+                .iter()
+                .filter(|pkg| !is_removed(&removed, &pkg.name))
+                .flat_map(|pkg| {
+                    pkg.depends
+                        .iter()
+                        .filter(|dep| {
+                            !self.installed.iter().any(|other| {
+                                !is_removed(&removed, &other.name) && other.satisfies(dep)
+                            }) && removed.iter().any(|other| other.satisfies(dep))
+                        })
+                        .map(move |dep| format!("{} requires {}", pkg.name, dep))
+                })
+                .collect();
+
+        if !missing.is_empty() {
+            return Err(Error::UnsatisfiedDependencies(missing));
+        }
+
+        Ok(Plan {
+            remove: removed.iter().map(|pkg| pkg.name.clone()).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn transaction_packages(&self, plan: &Plan) -> Vec<TransactionPackage> {
+        let installed = plan.install.iter().map(|pkg| {
+            let old_pkg = self.installed(&pkg.name);
+
+            TransactionPackage {
+                name: pkg.name.clone(),
+                old_version: old_pkg.map(|old| old.version.clone()),
+                new_version: Some(pkg.version.clone()),
+                old_size: old_pkg.map_or(0, |old| old.size),
+                new_size: pkg.size,
+                download_size: pkg.download_size,
+            }
+        });
+        let removed = plan
+            .remove
+            .iter()
+            .filter_map(|name| self.installed(name))
+            .map(|pkg| TransactionPackage {
+                name: pkg.name.clone(),
+                old_version: Some(pkg.version.clone()),
+                new_version: None,
+                old_size: pkg.size,
+                new_size: 0,
+                download_size: 0,
+            });
+
+        installed.chain(removed).collect()
+    }
+
+    /// Replace a backup file with its `leftover`, or delete it, returning what was done.
+    fn resolve_leftover(&mut self, leftover: &str, replace: bool) -> error::Result<String> {
+        let path = leftover_origin(leftover);
+        let file = self
+            .installed
+            .iter_mut()
+            .flat_map(|pkg| &mut pkg.files)
+            .find(|file| file.backup && Some(file.path.as_str()) == path);
+
+        let pacnew = leftover.ends_with(".pacnew");
+        let content = file.and_then(|file| {
+            let content = if pacnew {
+                file.pacnew.take()
+            } else {
+                file.pacsave.take()
+            };
+
+            // A .pacnew holds the packaged version, a .pacsave the one of the user
+            if replace && content.is_some() {
+                file.content = content.clone();
+                file.modified = !pacnew;
+                file.missing = false;
+            }
+
+            content
+        });
+
+        match (content, path) {
+            (Some(_), Some(path)) if replace => Ok(format!("replaced /{path} with /{leftover}")),
+            (Some(_), _) => Ok(format!("deleted /{leftover}")),
+            (None, _) => Err(Error::LeftoverError(
+                leftover.to_string(),
+                "no such file".to_string(),
+            )),
+        }
+    }
+
+    /// Log the changes of a transaction like alpm does in the pacman log.
+    fn log_transaction(&mut self, packages: &[TransactionPackage]) {
+        let date = Local::now().format("%Y-%m-%dT%H:%M:%S%z");
+        let mut log = |message: String| {
+            self.log.push_str(&format!("[{date}] [ALPM] {message}\n"));
+        };
+
+        log("transaction started".to_string());
+
+        for pkg in packages {
+            let message = match (&pkg.old_version, &pkg.new_version) {
+                (Some(old), Some(new)) if old == new => format!("reinstalled {} ({new})", pkg.name),
+                (Some(old), Some(new)) if is_newer(new, old) => {
+                    format!("upgraded {} ({old} -> {new})", pkg.name)
+                }
+                (Some(old), Some(new)) => format!("downgraded {} ({old} -> {new})", pkg.name),
+                (None, Some(new)) => format!("installed {} ({new})", pkg.name),
+                (Some(old), None) => format!("removed {} ({old})", pkg.name),
+                (None, None) => continue,
+            };
+
+            log(message);
+        }
+
+        log("transaction completed".to_string());
+    }
+
+    fn apply(&mut self, plan: Plan) {
+        self.installed
+            .retain(|pkg| !plan.remove.contains(&pkg.name));
+
+        for pkg in &mut self.installed {
+            if plan.mark_explicit.contains(&pkg.name) {
+                pkg.dependency = false;
+            }
+        }
+
+        for pkg in plan.install {
+            let pkg = FixturePackage {
+                install_date: Some(Local::now().timestamp()),
+                ..pkg
+            };
+
+            match self.installed.iter_mut().find(|old| old.name == pkg.name) {
+                Some(old) => *old = pkg,
+                None => self.installed.push(pkg),
+            }
+        }
+    }
+}
+
+impl FixturePackage {
+    /// Whether the package is, or provides, the name `dep` refers to.
+    fn satisfies(&self, dep: &str) -> bool {
+        let name = dep_name(dep);
+
+        self.name == name
+            || self
+                .provides
+                .iter()
+                .any(|provide| dep_name(provide) == name)
+    }
+
+    /// Whether the package, or one of its provides, meets the version required by `dep`, like
+    /// `glibc>=2.27`.
+    fn satisfies_version(&self, dep: &str) -> bool {
+        let Some((name, constraint, version)) = ["<=", ">=", "<", ">", "="].iter().find_map(|op| {
+            dep.split_once(op)
+                .map(|(name, version)| (name, *op, version))
+        }) else {
+            return self.satisfies(dep);
+        };
+
+        let provided = self
+            .provides
+            .iter()
+            .map(|provide| match provide.split_once('=') {
+                Some((name, version)) => (name, Some(version)),
+                None => (provide.as_str(), None),
+            });
+
+        iter::once((self.name.as_str(), Some(self.version.as_str())))
+            .chain(provided)
+            .any(|(provider, provided)| {
+                provider == name
+                    && provided.is_some_and(|provided| {
+                        let order = vercmp(provided, version);
+
+                        match constraint {
+                            "<=" => order.is_le(),
+                            ">=" => order.is_ge(),
+                            "<" => order.is_lt(),
+                            ">" => order.is_gt(),
+                            _ => order.is_eq(),
+                        }
+                    })
+            })
+    }
+
+    /// Report of the files flagged missing or modified.
+    fn integrity_report(&self) -> IntegrityReport {
+        let issues = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let issue = if file.missing {
+                    FileIssue::Missing
+                } else if file.modified {
+                    FileIssue::Checksum
+                } else {
+                    return None;
+                };
+
+                Some((file.path.clone(), issue))
+            })
+            .collect();
+
+        IntegrityReport {
+            package: self.name.clone(),
+            checked: self.files.len(),
+            issues,
+            error: None,
+        }
+    }
+
+    fn install_date(&self) -> Option<DateTime<Local>> {
+        self.install_date.and_then(local_datetime)
+    }
+
+    fn package_data(&self) -> PackageData {
+        PackageData {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            new_version: None,
+            description: self.description.clone(),
+            architecture: self.architecture.clone(),
+            url: self.url.clone(),
+            repository: None,
+            installed: false,
+            explicit: false,
+            groups: self.groups.clone(),
+            licenses: self.licenses.clone(),
+            provides: self.provides.clone(),
+            dependencies: self.depends.clone(),
+            optional_dependencies: self.optdepends.clone(),
+            conflicts: self.conflicts.clone(),
+            replaces: self.replaces.clone(),
+            required_by: Vec::new(),
+            optional_for: Vec::new(),
+            size: self.size,
+            packager: self.packager.clone(),
+            install_date: self.install_date(),
+            build_date: self.build_date.and_then(local_datetime),
+        }
+    }
+}
+
+impl FixtureFile {
+    /// File of an installed package, on disk unless missing.
+    fn installed(&self) -> PackageFile {
+        PackageFile {
+            disk_size: (!self.missing).then_some(self.size as u64),
+            ..self.available()
+        }
+    }
+
+    /// Backup file of the installed package `package`.
+    fn backup_file(&self, package: &str) -> BackupFile {
+        let leftovers = [(".pacnew", &self.pacnew), (".pacsave", &self.pacsave)]
+            .into_iter()
+            .filter(|(_, content)| content.is_some())
+            .map(|(suffix, _)| format!("{}{suffix}", self.path))
+            .collect();
+
+        BackupFile {
+            package: package.to_string(),
+            path: self.path.clone(),
+            status: if self.missing {
+                BackupStatus::Missing
+            } else if self.modified {
+                BackupStatus::Modified
+            } else {
+                BackupStatus::Unmodified
+            },
+            leftovers,
+        }
+    }
+
+    /// Content of the file, or of a leftover next to it, at `path`.
+    fn read(&self, path: &str) -> Option<String> {
+        match path.strip_prefix(self.path.as_str())? {
+            "" if !self.missing => Some(self.content.clone().unwrap_or_default()),
+            ".pacnew" => self.pacnew.clone(),
+            ".pacsave" => self.pacsave.clone(),
+            _ => None,
+        }
+    }
+
+    /// File of a package that is not installed.
+    fn available(&self) -> PackageFile {
+        PackageFile {
+            path: self.path.clone(),
+            size: self.size,
+            disk_size: None,
+        }
+    }
+}
+
+fn local_datetime(timestamp: i64) -> Option<DateTime<Local>> {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|datetime_utc| Local.from_utc_datetime(&datetime_utc.naive_utc()))
+}
+
+/// Name part of a dependency, without its version constraint or description.
+fn dep_name(dep: &str) -> &str {
+    dep.split(['<', '>', '=', ':']).next().unwrap_or(dep).trim()
+}
+
+/// Play a transaction on the fixture, reporting each step through `events`.
+fn simulate_transaction(
+    fixture: &Mutex<Fixture>,
+    transaction: &Transaction,
+    events: &UnboundedSender<TransactionEvent>,
+) -> error::Result<Vec<TransactionPackage>> {
+    let (plan, packages) = {
+        let fixture = fixture.lock().unwrap_or_else(|err| err.into_inner());
+        let plan = fixture.plan(transaction)?;
+        let packages = fixture.transaction_packages(&plan);
+
+        (plan, packages)
+    };
+
+    let log = |line: String| _ = events.send(TransactionEvent::Log(line));
+
+    if let Transaction::Refresh | Transaction::RefreshSystem = transaction {
+        log("synchronizing package databases...".to_string());
+
+        let repositories: Vec<String> = fixture
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .repositories
+            .iter()
+            .map(|repo| repo.name.clone())
+            .collect();
+
+        for repo in repositories {
+            thread::sleep(STEP_DELAY);
+            _ = events.send(TransactionEvent::Download {
+                file: format!("{repo}.db"),
+                downloaded: REPOSITORY_DB_SIZE,
+                total: REPOSITORY_DB_SIZE,
+            });
+        }
+
+        return Ok(packages);
+    }
+
+    if let Transaction::ReplaceBackup(leftover) | Transaction::DeleteLeftover(leftover) =
+        transaction
+    {
+        thread::sleep(STEP_DELAY);
+        let replace = matches!(transaction, Transaction::ReplaceBackup(_));
+        let message = fixture
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .resolve_leftover(leftover, replace)?;
+        log(message);
+
+        return Ok(packages);
+    }
+
+    // Install reasons change once the packages are, like with pacman
+    let marked = plan.mark_explicit.clone();
+    let log_marked = || {
+        for name in &marked {
+            log(format!(
+                "{name}: install reason has been set to 'explicitly installed'"
+            ));
+        }
+    };
+
+    log("resolving dependencies...".to_string());
+    if packages.is_empty() {
+        fixture
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .apply(plan);
+        log_marked();
+
+        return Ok(packages);
+    }
+
+    for pkg in packages.iter().filter(|pkg| pkg.download_size > 0) {
+        thread::sleep(STEP_DELAY);
+        _ = events.send(TransactionEvent::Download {
+            file: format!(
+                "{}-{}",
+                pkg.name,
+                pkg.new_version.as_deref().unwrap_or_default()
+            ),
+            downloaded: pkg.download_size,
+            total: pkg.download_size,
+        });
+    }
+
+    log("processing package changes...".to_string());
+    for (index, pkg) in packages.iter().enumerate() {
+        let (operation, message) = match (&pkg.old_version, &pkg.new_version) {
+            (Some(old), Some(new)) => (
+                "upgrading",
+                format!("upgraded {} ({old} -> {new})", pkg.name),
+            ),
+            (None, Some(new)) => ("installing", format!("installed {} ({new})", pkg.name)),
+            (Some(old), None) => ("removing", format!("removed {} ({old})", pkg.name)),
+            (None, None) => continue,
+        };
+
+        thread::sleep(STEP_DELAY);
+        _ = events.send(TransactionEvent::Progress {
+            operation: operation.to_string(),
+            package: pkg.name.clone(),
+            percent: 100,
+            current: index + 1,
+            total: packages.len(),
+        });
+        log(message);
+    }
+
+    let mut fixture = fixture.lock().unwrap_or_else(|err| err.into_inner());
+    fixture.log_transaction(&packages);
+    fixture.apply(plan);
+    log_marked();
+
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backend(fixture: &str) -> FixtureBackend {
+        FixtureBackend {
+            fixture: Arc::new(Mutex::new(toml::from_str(fixture).unwrap())),
+        }
+    }
+
+    const PARTIAL_UPGRADE: &str = r#"
+[[installed]]
+name = "foo"
+version = "1.0-1"
+depends = ["libbar"]
+
+[[installed]]
+name = "libbar"
+version = "1.0-1"
+provides = ["libbar.so=1-64"]
+
+[[installed]]
+name = "libbaz"
+version = "1.0-1"
+
+[[repositories]]
+name = "extra"
+
+[[repositories.packages]]
+name = "foo"
+version = "2.0-1"
+depends = ["libbar>=2.0", "libbaz"]
+
+[[repositories.packages]]
+name = "qux"
+version = "1.0-1"
+depends = ["libbar>=1.0", "libbar.so=1-64", "libbaz=1.0-1"]
+
+[[repositories.packages]]
+name = "libbar"
+version = "1.0-1"
+"#;
+
+    #[test]
+    fn partial_upgrade_flags_dependencies_too_old() {
+        let backend = backend(PARTIAL_UPGRADE);
+        let partial_upgrade = backend.partial_upgrade(&HashSet::from(["foo".to_string()]));
+
+        assert!(partial_upgrade.skipped_upgrades.is_empty());
+        assert_eq!(
+            partial_upgrade.outdated_dependencies,
+            vec![("libbar".to_string(), "foo".to_string())]
+        );
+    }
+
+    #[test]
+    fn partial_upgrade_accepts_satisfied_versioned_dependencies() {
+        let backend = backend(PARTIAL_UPGRADE);
+        let partial_upgrade = backend.partial_upgrade(&HashSet::from(["qux".to_string()]));
+
+        assert!(!partial_upgrade.is_partial());
+    }
+
+    #[test]
+    fn versioned_dependencies_compare_versions() {
+        let pkg = FixturePackage {
+            name: "readline".to_string(),
+            version: "8.2.013-2".to_string(),
+            provides: vec!["libreadline.so=8-64".to_string(), "libhistory".to_string()],
+            ..Default::default()
+        };
+
+        assert!(pkg.satisfies_version("readline"));
+        assert!(pkg.satisfies_version("readline>=8.2"));
+        assert!(pkg.satisfies_version("readline<9"));
+        assert!(!pkg.satisfies_version("readline>8.2.013-2"));
+        assert!(!pkg.satisfies_version("readline=8.1"));
+        assert!(pkg.satisfies_version("libreadline.so=8-64"));
+        assert!(pkg.satisfies_version("libhistory"));
+        // Unversioned provides never satisfy a versioned dependency
+        assert!(!pkg.satisfies_version("libhistory>=1"));
+    }
+
+    #[test]
+    fn demo_fixture_loads() {
+        let backend =
+            FixtureBackend::load(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/demo.toml"))
+                .unwrap();
+
+        assert!(backend.packages().any(|pkg| pkg.name == "bash"));
+        assert!(backend.repository_packages().count() > 0);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::backend::{HistoryAction, HistoryEntry, HistoryTransaction};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+
+/// Transactions of a pacman log, oldest first.
+///
+/// Package changes logged outside of a `transaction started` line, like in old logs, are
+/// grouped until the next transaction.
+pub fn parse(content: &str) -> Vec<HistoryTransaction> {
+    let mut transactions = Vec::new();
+    // Command pacman logged for the transactions to come
+    let mut command = None;
+    let mut current: Option<HistoryTransaction> = None;
+
+    for line in content.lines() {
+        let Some((date, source, message)) = split_line(line) else {
+            continue;
+        };
+
+        match (source, message) {
+            ("PACMAN", message) => {
+                if let Some(running) = message
+                    .strip_prefix("Running '")
+                    .and_then(|running| running.strip_suffix('\''))
+                {
+                    command = Some(running.to_string());
+                }
+            }
+            ("ALPM", "transaction started") => {
+                transactions.extend(current.take());
+                current = Some(HistoryTransaction {
+                    date,
+                    command: command.take(),
+                    entries: Vec::new(),
+                });
+            }
+            (
+                "ALPM",
+                "transaction completed" | "transaction failed" | "transaction interrupted",
+            ) => transactions.extend(current.take()),
+            ("ALPM", message) => {
+                if let Some(entry) = parse_entry(date, message) {
+                    current
+                        .get_or_insert_with(|| HistoryTransaction {
+                            date,
+                            command: command.take(),
+                            entries: Vec::new(),
+                        })
+                        .entries
+                        .push(entry);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    transactions.extend(current);
+    transactions.retain(|transaction| !transaction.entries.is_empty());
+    transactions
+}
+
+/// Date, source and message of a line like `[2025-05-01T10:00:00+0200] [ALPM] message`.
+fn split_line(line: &str) -> Option<(DateTime<Local>, &str, &str)> {
+    let (date, rest) = line.strip_prefix('[')?.split_once("] [")?;
+    let (source, message) = rest.split_once("] ")?;
+
+    Some((parse_date(date)?, source, message))
+}
+
+/// Dates are logged in RFC 3339 since pacman 5.1, and in local time without seconds before.
+fn parse_date(date: &str) -> Option<DateTime<Local>> {
+    match DateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%z") {
+        Ok(date) => Some(date.with_timezone(&Local)),
+        Err(_) => NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M")
+            .ok()
+            .and_then(|date| Local.from_local_datetime(&date).earliest()),
+    }
+}
+
+/// Package change of a message like `upgraded bash (5.2.037-1 -> 5.2.037-5)`.
+fn parse_entry(date: DateTime<Local>, message: &str) -> Option<HistoryEntry> {
+    let (verb, rest) = message.split_once(' ')?;
+    let (package, versions) = rest.split_once(" (")?;
+    let versions = versions.strip_suffix(')')?;
+
+    let action = match verb {
+        "installed" => HistoryAction::Installed,
+        "upgraded" => HistoryAction::Upgraded,
+        "downgraded" => HistoryAction::Downgraded,
+        "reinstalled" => HistoryAction::Reinstalled,
+        "removed" => HistoryAction::Removed,
+        _ => return None,
+    };
+
+    let (old_version, new_version) = match (action, versions.split_once(" -> ")) {
+        (_, Some((old, new))) => (Some(old), Some(new)),
+        (HistoryAction::Installed, None) => (None, Some(versions)),
+        (HistoryAction::Removed, None) => (Some(versions), None),
+        (_, None) => (Some(versions), Some(versions)),
+    };
+
+    Some(HistoryEntry {
+        date,
+        action,
+        package: package.to_string(),
+        old_version: old_version.map(String::from),
+        new_version: new_version.map(String::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+
+    #[test]
+    fn transactions_are_split_on_alpm_lines() {
+        let log = "\
+[2025-05-01T10:00:00+0200] [PACMAN] Running 'pacman -Syu'
+[2025-05-01T10:00:01+0200] [PACMAN] synchronizing package lists
+[2025-05-01T10:00:05+0200] [ALPM] transaction started
+[2025-05-01T10:00:06+0200] [ALPM] upgraded bash (5.2.037-1 -> 5.2.037-5)
+[2025-05-01T10:00:06+0200] [ALPM-SCRIPTLET] some output
+[2025-05-01T10:00:07+0200] [ALPM] installed htop (3.3.0-3)
+[2025-05-01T10:00:08+0200] [ALPM] transaction completed
+[2025-05-02T09:00:00+0200] [PACMAN] Running 'pacman -Rs htop'
+[2025-05-02T09:00:01+0200] [ALPM] transaction started
+[2025-05-02T09:00:02+0200] [ALPM] removed htop (3.3.0-3)
+[2025-05-02T09:00:03+0200] [ALPM] transaction failed
+[2025-05-03T09:00:01+0200] [ALPM] transaction started
+[2025-05-03T09:00:03+0200] [ALPM] transaction completed
+";
+        let transactions = parse(log);
+
+        // The empty transaction is left out
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].command.as_deref(), Some("pacman -Syu"));
+        assert_eq!(transactions[1].command.as_deref(), Some("pacman -Rs htop"));
+
+        let entries = &transactions[0].entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, HistoryAction::Upgraded);
+        assert_eq!(entries[0].package, "bash");
+        assert_eq!(entries[0].old_version.as_deref(), Some("5.2.037-1"));
+        assert_eq!(entries[0].new_version.as_deref(), Some("5.2.037-5"));
+        assert_eq!(entries[1].action, HistoryAction::Installed);
+        assert_eq!(entries[1].old_version, None);
+        assert_eq!(entries[1].new_version.as_deref(), Some("3.3.0-3"));
+
+        let removed = &transactions[1].entries[0];
+        assert_eq!(removed.action, HistoryAction::Removed);
+        assert_eq!(removed.old_version.as_deref(), Some("3.3.0-3"));
+        assert_eq!(removed.new_version, None);
+    }
+
+    #[test]
+    fn old_logs_are_grouped_until_the_next_transaction() {
+        let log = "\
+[2012-03-04 15:11] [PACMAN] Running 'pacman -S vim'
+[2012-03-04 15:11] [ALPM] installed vim (7.3.446-1)
+[2012-03-04 15:11] [ALPM] upgraded vi (1:050325-1 -> 1:070224-1)
+[2018-06-01T12:00:00+0000] [ALPM] transaction started
+[2018-06-01T12:00:01+0000] [ALPM] downgraded vim (8.1.0-1 -> 8.0.0-1)
+";
+        let transactions = parse(log);
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].command.as_deref(), Some("pacman -S vim"));
+        assert_eq!(transactions[0].entries.len(), 2);
+        assert_eq!(transactions[0].date.year(), 2012);
+        assert_eq!(transactions[0].entries[1].action, HistoryAction::Upgraded);
+        assert_eq!(transactions[1].entries[0].action, HistoryAction::Downgraded);
+    }
+
+    #[test]
+    fn both_date_formats_are_read() {
+        let new = parse_date("2025-05-01T10:00:00+0200").unwrap();
+        assert_eq!(new.timestamp(), 1746086400);
+
+        let old = parse_date("2012-03-04 15:11").unwrap();
+        assert_eq!(
+            old.naive_local().format("%Y-%m-%d %H:%M").to_string(),
+            "2012-03-04 15:11"
+        );
+
+        assert!(parse_date("04/03/2012").is_none());
+    }
+}
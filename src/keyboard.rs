@@ -16,13 +16,28 @@ pub enum Events {
     Quit,
     Back,
     Search,
+    SearchMode,
     Confirm,
     Filter,
+    SwitchView,
+    TableView,
+    SortKey,
+    SortDirection,
     Select,
     SelectUpgradables,
     Navigate(Move),
     Tab(Move),
     Sync,
+    Remove,
+    SystemUpgrade,
+    Refresh,
+    Orphans,
+    MarkExplicit,
+    DependencyTree,
+    Owner,
+    IntegrityCheck,
+    Backups,
+    History,
 }
 
 #[derive(Debug)]
@@ -46,46 +61,53 @@ pub async fn read_event() -> KeyboardEvent {
     loop {
         let event = reader.next().fuse();
 
-        if let Some(Ok(raw)) = event.await {
-            if let CrosstermEvent::Key(key) = raw {
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
+        if let Some(Ok(raw)) = event.await
+            && let CrosstermEvent::Key(key) = raw
+        {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
 
-                let event: Option<Events> = match (key.modifiers, key.code) {
-                    (_, KeyCode::Char('j')) | (_, KeyCode::Down) => {
-                        Some(Events::Navigate(Move::Next))
-                    }
-                    (_, KeyCode::Char('k')) | (_, KeyCode::Up) => {
-                        Some(Events::Navigate(Move::Previous))
-                    }
-                    (_, KeyCode::Char('g')) | (_, KeyCode::Home) => {
-                        Some(Events::Navigate(Move::First))
-                    }
-                    (_, KeyCode::Char('G')) | (_, KeyCode::End) => {
-                        Some(Events::Navigate(Move::Last))
-                    }
-                    (_, KeyCode::Tab) => Some(Events::Tab(Move::Next)),
-                    (_, KeyCode::BackTab) => Some(Events::Tab(Move::Previous)),
-                    (_, KeyCode::Char('x')) => Some(Events::Select),
-                    (_, KeyCode::Char('/')) => Some(Events::Search),
-                    (_, KeyCode::Char('q')) => Some(Events::Quit),
-                    (_, KeyCode::Esc) => Some(Events::Back),
-                    (_, KeyCode::Enter) => Some(Events::Confirm),
-                    (KeyModifiers::CONTROL, KeyCode::Char('u')) => {
-                        Some(Events::Navigate(Move::JumpUp))
-                    }
-                    (KeyModifiers::CONTROL, KeyCode::Char('d')) => {
-                        Some(Events::Navigate(Move::JumpDown))
-                    }
-                    (KeyModifiers::ALT, KeyCode::Char('u')) => Some(Events::Filter),
-                    (KeyModifiers::SHIFT, KeyCode::Char('X')) => Some(Events::SelectUpgradables),
-                    (KeyModifiers::SHIFT, KeyCode::Char('S')) => Some(Events::Sync),
-                    _ => None,
-                };
+            let event: Option<Events> = match (key.modifiers, key.code) {
+                (_, KeyCode::Char('j')) | (_, KeyCode::Down) => Some(Events::Navigate(Move::Next)),
+                (_, KeyCode::Char('k')) | (_, KeyCode::Up) => {
+                    Some(Events::Navigate(Move::Previous))
+                }
+                (_, KeyCode::Char('g')) | (_, KeyCode::Home) => Some(Events::Navigate(Move::First)),
+                (_, KeyCode::Char('G')) | (_, KeyCode::End) => Some(Events::Navigate(Move::Last)),
+                (_, KeyCode::Tab) => Some(Events::Tab(Move::Next)),
+                (_, KeyCode::BackTab) => Some(Events::Tab(Move::Previous)),
+                (_, KeyCode::Char('x')) => Some(Events::Select),
+                (_, KeyCode::Char('e')) => Some(Events::MarkExplicit),
+                (_, KeyCode::Char('/')) => Some(Events::Search),
+                (_, KeyCode::Char('q')) => Some(Events::Quit),
+                (_, KeyCode::Esc) => Some(Events::Back),
+                (_, KeyCode::Enter) => Some(Events::Confirm),
+                (KeyModifiers::CONTROL, KeyCode::Char('u')) => Some(Events::Navigate(Move::JumpUp)),
+                (KeyModifiers::CONTROL, KeyCode::Char('d')) => {
+                    Some(Events::Navigate(Move::JumpDown))
+                }
+                (KeyModifiers::ALT, KeyCode::Char('u')) => Some(Events::Filter),
+                (KeyModifiers::ALT, KeyCode::Char('r')) => Some(Events::SwitchView),
+                (KeyModifiers::ALT, KeyCode::Char('t')) => Some(Events::TableView),
+                (KeyModifiers::ALT, KeyCode::Char('m')) => Some(Events::SearchMode),
+                (KeyModifiers::ALT, KeyCode::Char('s')) => Some(Events::SortKey),
+                (KeyModifiers::ALT, KeyCode::Char('d')) => Some(Events::SortDirection),
+                (KeyModifiers::SHIFT, KeyCode::Char('X')) => Some(Events::SelectUpgradables),
+                (KeyModifiers::SHIFT, KeyCode::Char('S')) => Some(Events::Sync),
+                (KeyModifiers::SHIFT, KeyCode::Char('R')) => Some(Events::Remove),
+                (KeyModifiers::SHIFT, KeyCode::Char('U')) => Some(Events::SystemUpgrade),
+                (KeyModifiers::SHIFT, KeyCode::Char('Y')) => Some(Events::Refresh),
+                (KeyModifiers::SHIFT, KeyCode::Char('O')) => Some(Events::Orphans),
+                (KeyModifiers::SHIFT, KeyCode::Char('T')) => Some(Events::DependencyTree),
+                (KeyModifiers::SHIFT, KeyCode::Char('F')) => Some(Events::Owner),
+                (KeyModifiers::SHIFT, KeyCode::Char('K')) => Some(Events::IntegrityCheck),
+                (KeyModifiers::SHIFT, KeyCode::Char('B')) => Some(Events::Backups),
+                (KeyModifiers::SHIFT, KeyCode::Char('H')) => Some(Events::History),
+                _ => None,
+            };
 
-                return KeyboardEvent { event, raw };
-            }
+            return KeyboardEvent { event, raw };
         }
     }
 }
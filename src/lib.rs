@@ -1,7 +1,12 @@
 mod utils;
 
 pub mod app;
+pub mod backend;
 pub mod config;
 pub mod error;
+pub mod fixture;
+mod history;
 pub mod keyboard;
+mod mtree;
 pub mod pacman;
+pub mod privilege;
@@ -1,22 +1,42 @@
-use nix::unistd::Uid;
-use std::process::exit;
-use tecarius::{app::App, config, error, pacman::Pacman};
+use std::env;
+use tecarius::{
+    app::App,
+    backend::PackageBackend,
+    config, error,
+    fixture::FixtureBackend,
+    pacman::Pacman,
+    privilege::{self, HELPER_ARG},
+};
 
 #[tokio::main]
 async fn main() -> error::Result<()> {
-    // Get color configuration
-    let theme_colors = config::theme_colors().unwrap_or_default();
+    // Run a transaction as the privileged helper of another instance
+    if let Some(transaction) = env::args().skip_while(|arg| arg != HELPER_ARG).nth(1) {
+        return privilege::run_helper(&transaction).await;
+    }
+
+    // Get user and color configuration
+    let user_config = config::user_config().unwrap_or_default();
+    let theme_colors = config::theme_colors(&user_config).unwrap_or_default();
+
+    // Run against a fake package database when given `--fixture <path>`
+    let fixture_path = env::args().skip_while(|arg| arg != "--fixture").nth(1);
+
+    let backend: Box<dyn PackageBackend> = match fixture_path {
+        Some(path) => Box::new(FixtureBackend::load(path)?),
+        None => Box::new(
+            Pacman::open(user_config.refresh_mode)?
+                .with_privilege_helper(user_config.privilege_helper),
+        ),
+    };
 
-    // Check super-user rights
-    if !Uid::effective().is_root() {
-        eprintln!("Tecarius must be run with root permissions.");
-        exit(1);
+    let mut terminal = ratatui::init();
+    let mut app = App::new(backend, theme_colors, &user_config);
+    if user_config.refresh_on_startup {
+        app.refresh_databases();
     }
 
-    let pacman = Pacman::new()?;
-    let result = App::new(pacman, theme_colors)
-        .run(&mut ratatui::init())
-        .await;
+    let result = app.run(&mut terminal).await;
 
     // Restore terminal
     ratatui::restore();
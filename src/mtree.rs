@@ -0,0 +1,348 @@
+use crate::backend::{FileIssue, IntegrityEvent, IntegrityReport};
+use alpm::compute_sha256sum;
+use flate2::read::GzDecoder;
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read},
+    os::unix::{ffi::OsStringExt, fs::MetadataExt},
+    path::{Path, PathBuf},
+    thread,
+};
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+/// Installed package whose files are checked against its mtree data.
+pub struct MtreeTarget {
+    pub package: String,
+    /// Gzip compressed mtree file of the package in the local database.
+    pub mtree: PathBuf,
+    /// Backup files, which are expected to be modified.
+    pub backup: HashSet<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum EntryType {
+    #[default]
+    File,
+    Dir,
+    Link,
+}
+
+/// File metadata recorded in an mtree when the package was built.
+#[derive(Debug, Default, Clone)]
+struct Entry {
+    /// Path relative to the root, as shown.
+    path: String,
+    /// Path relative to the root, as on disk, which may not be valid UTF-8.
+    file: PathBuf,
+    kind: EntryType,
+    mode: Option<u32>,
+    size: Option<u64>,
+    /// Unix timestamp of the last modification.
+    time: Option<i64>,
+    sha256: Option<String>,
+    /// Target of a symlink.
+    link: Option<PathBuf>,
+}
+
+/// Check the files of `targets` installed under `root` in the background, like
+/// `pacman -Qkk`.
+pub fn spawn_check(root: PathBuf, targets: Vec<MtreeTarget>) -> UnboundedReceiver<IntegrityEvent> {
+    let (sender, receiver) = unbounded_channel();
+
+    thread::spawn(move || {
+        _ = sender.send(IntegrityEvent::Started(targets.len()));
+
+        for target in &targets {
+            let report = check_package(&root, target);
+
+            // Stop once the check is not followed anymore
+            if sender.send(IntegrityEvent::Checked(report)).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+fn check_package(root: &Path, target: &MtreeTarget) -> IntegrityReport {
+    let mut report = IntegrityReport {
+        package: target.package.clone(),
+        ..Default::default()
+    };
+
+    let entries = match read(&target.mtree) {
+        Ok(content) => parse(&content),
+        Err(err) => {
+            report.error = Some(err.to_string());
+            return report;
+        }
+    };
+
+    for entry in &entries {
+        let backup = target.backup.contains(&entry.path);
+        let mut issues = check_entry(root, entry, backup);
+
+        // Checksums are only computed once the cheaper checks passed
+        if issues.is_empty()
+            && !backup
+            && let Some(recorded) = &entry.sha256
+        {
+            match compute_sha256sum(root.join(&entry.file).into_os_string().into_vec()) {
+                Ok(actual) if actual == *recorded => (),
+                Ok(_) => issues.push(FileIssue::Checksum),
+                Err(err) => issues.push(FileIssue::Unreadable(err.to_string())),
+            }
+        }
+
+        report
+            .issues
+            .extend(issues.into_iter().map(|issue| (entry.path.clone(), issue)));
+    }
+
+    report.checked = entries.len();
+    report.issues.sort_by(|(a, _), (b, _)| a.cmp(b));
+    report
+}
+
+/// Differences between the file of `entry` on disk and its recorded metadata.
+///
+/// Like pacman, only the type and permissions of backup files are checked.
+fn check_entry(root: &Path, entry: &Entry, backup: bool) -> Vec<FileIssue> {
+    let path = root.join(&entry.file);
+
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return vec![FileIssue::Missing],
+        Err(err) => return vec![FileIssue::Unreadable(err.to_string())],
+    };
+
+    let file_type = metadata.file_type();
+    let same_type = match entry.kind {
+        EntryType::File => file_type.is_file(),
+        EntryType::Dir => file_type.is_dir(),
+        EntryType::Link => file_type.is_symlink(),
+    };
+
+    if !same_type {
+        return vec![FileIssue::Type];
+    }
+
+    let mut issues = Vec::new();
+
+    if entry.kind != EntryType::Link
+        && let Some(recorded) = entry.mode
+        && metadata.mode() & 0o7777 != recorded
+    {
+        issues.push(FileIssue::Permissions {
+            recorded,
+            actual: metadata.mode() & 0o7777,
+        });
+    }
+
+    match entry.kind {
+        EntryType::Link => {
+            if let Some(recorded) = &entry.link {
+                match fs::read_link(&path) {
+                    Ok(target) if target == *recorded => (),
+                    Ok(target) => issues.push(FileIssue::LinkTarget {
+                        recorded: recorded.display().to_string(),
+                        actual: target.display().to_string(),
+                    }),
+                    Err(err) => issues.push(FileIssue::Unreadable(err.to_string())),
+                }
+            }
+        }
+        EntryType::File if !backup => {
+            if let Some(recorded) = entry.size
+                && metadata.len() != recorded
+            {
+                issues.push(FileIssue::Size {
+                    recorded,
+                    actual: metadata.len(),
+                });
+            }
+
+            if let Some(recorded) = entry.time
+                && metadata.mtime() != recorded
+            {
+                issues.push(FileIssue::ModificationTime {
+                    recorded,
+                    actual: metadata.mtime(),
+                });
+            }
+        }
+        _ => (),
+    }
+
+    issues
+}
+
+/// Read a gzip compressed mtree file.
+fn read(path: &Path) -> io::Result<String> {
+    let mut content = Vec::new();
+    GzDecoder::new(File::open(path)?).read_to_end(&mut content)?;
+
+    // Paths are escaped, the rest of the file is ASCII
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// Entries of an mtree, without the package metadata files like `.PKGINFO`.
+fn parse(content: &str) -> Vec<Entry> {
+    let mut defaults = Entry::default();
+    let mut entries = Vec::new();
+
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("/set") => words.for_each(|word| set_keyword(&mut defaults, word)),
+            // Packages only unset everything, if anything
+            Some("/unset") => defaults = Entry::default(),
+            Some(path) => {
+                let Some(path) = path.strip_prefix("./") else {
+                    continue;
+                };
+
+                if path.starts_with('.') && !path.contains('/') {
+                    continue;
+                }
+
+                let file = PathBuf::from(OsString::from_vec(unescape(path)));
+                let mut entry = Entry {
+                    path: file.to_string_lossy().into_owned(),
+                    file,
+                    ..defaults.clone()
+                };
+                words.for_each(|word| set_keyword(&mut entry, word));
+                entries.push(entry);
+            }
+            None => (),
+        }
+    }
+
+    entries
+}
+
+fn set_keyword(entry: &mut Entry, word: &str) {
+    let Some((keyword, value)) = word.split_once('=') else {
+        return;
+    };
+
+    match keyword {
+        "type" => {
+            entry.kind = match value {
+                "dir" => EntryType::Dir,
+                "link" => EntryType::Link,
+                _ => EntryType::File,
+            }
+        }
+        "mode" => entry.mode = u32::from_str_radix(value, 8).ok(),
+        "size" => entry.size = value.parse().ok(),
+        "time" => entry.time = value.split('.').next().and_then(|secs| secs.parse().ok()),
+        "sha256digest" => entry.sha256 = Some(value.to_string()),
+        "link" => entry.link = Some(PathBuf::from(OsString::from_vec(unescape(value)))),
+        _ => (),
+    }
+}
+
+/// Decode the `\ooo` octal escapes of mtree paths, which may not be valid UTF-8.
+fn unescape(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escape = bytes.get(index + 1..index + 4).filter(|digits| {
+            bytes[index] == b'\\' && digits.iter().all(|digit| (b'0'..=b'7').contains(digit))
+        });
+
+        match escape {
+            Some(digits) => {
+                let byte = digits
+                    .iter()
+                    .fold(0u32, |byte, digit| byte * 8 + u32::from(digit - b'0'));
+                decoded.push(byte as u8);
+                index += 4;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MTREE: &str = r"#mtree
+/set type=file uid=0 gid=0 mode=644
+./.BUILDINFO time=1700000000.0 size=4000 sha256digest=aa
+./.PKGINFO time=1700000000.0 size=600 sha256digest=bb
+./etc time=1700000000.0 mode=755 type=dir
+./etc/bash.bashrc time=1700000000.5 size=1200 sha256digest=cc
+./usr/share/doc/My\040Notes.txt size=10 sha256digest=dd
+./usr/lib/libfoo.so time=1700000000.0 type=link link=libfoo\056so.1
+/set mode=755
+./usr/bin/bash size=1000 sha256digest=ee
+";
+
+    #[test]
+    fn entries_inherit_set_keywords() {
+        let entries = parse(MTREE);
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "etc",
+                "etc/bash.bashrc",
+                "usr/share/doc/My Notes.txt",
+                "usr/lib/libfoo.so",
+                "usr/bin/bash",
+            ]
+        );
+
+        assert_eq!(entries[0].kind, EntryType::Dir);
+        assert_eq!(entries[0].mode, Some(0o755));
+        assert_eq!(entries[1].kind, EntryType::File);
+        assert_eq!(entries[1].mode, Some(0o644));
+        assert_eq!(entries[1].size, Some(1200));
+        assert_eq!(entries[1].time, Some(1700000000));
+        assert_eq!(entries[1].sha256.as_deref(), Some("cc"));
+        assert_eq!(entries[3].kind, EntryType::Link);
+        assert_eq!(entries[3].link.as_deref(), Some(Path::new("libfoo.so.1")));
+        assert_eq!(entries[4].mode, Some(0o755));
+    }
+
+    #[test]
+    fn octal_escapes_are_decoded() {
+        assert_eq!(unescape(r"a\040b\043c"), b"a b#c");
+        assert_eq!(unescape(r"caf\303\251"), "café".as_bytes());
+        assert_eq!(unescape(r"latin\351"), b"latin\xe9");
+        // Anything else is kept as is
+        assert_eq!(unescape(r"a\09b\\c\12"), br"a\09b\\c\12");
+    }
+
+    #[test]
+    fn gzip_mtree_is_read() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("tecarius-mtree-{}", std::process::id()));
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        encoder.write_all(MTREE.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let content = read(&path);
+        _ = fs::remove_file(&path);
+
+        assert_eq!(content.unwrap(), MTREE);
+    }
+}
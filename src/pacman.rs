@@ -1,116 +1,1300 @@
-use crate::{error, utils::to_human_bytes};
-use alpm::{Alpm, AlpmList, Dep, SigLevel, Ver, vercmp};
+use crate::{
+    backend::{
+        BackupFile, BackupStatus, FileOwner, HistoryTransaction, IntegrityEvent, PackageBackend,
+        PackageData, PackageFile, PartialUpgrade, Privileges, RemoveMode, Transaction,
+        TransactionEvent, TransactionPackage, file_matches, is_newer, leftover_origin,
+        resolve_query,
+    },
+    config::{PrivilegeHelper, RefreshMode},
+    error::{self, Error},
+    history,
+    mtree::{self, MtreeTarget},
+    privilege,
+    utils::to_human_bytes,
+};
+use alpm::{
+    Alpm, AlpmList, CommitData, Dep, DownloadEvent, Event, HookWhen, LogLevel, Package,
+    PackageOperation, PackageReason, PrepareData, Progress, Question, SigLevel, TransFlag,
+    compute_md5sum,
+};
 use chrono::{DateTime, Local, TimeZone};
+use nix::unistd::Uid;
 use pacmanconf::Config;
 use std::{
-    cmp::Ordering,
-    process::{ChildStdout, Command, Stdio},
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    env, fs, io,
+    os::unix::{ffi::OsStrExt, fs::symlink},
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
 };
-
-pub struct PackageData<'a> {
-    pub name: &'a str,
-    pub version: &'a Ver,
-    pub new_version: Option<&'a Ver>,
-    pub description: Option<&'a str>,
-    pub architecture: Option<&'a str>,
-    pub url: Option<&'a str>,
-    pub licenses: Vec<String>,
-    pub provides: AlpmList<'a, &'a Dep>,
-    pub dependencies: AlpmList<'a, &'a Dep>,
-    pub optional_dependencies: AlpmList<'a, &'a Dep>,
-    pub conflicts: AlpmList<'a, &'a Dep>,
-    pub replaces: AlpmList<'a, &'a Dep>,
-    pub size: String,
-    pub packager: Option<&'a str>,
-    pub install_date: Option<DateTime<Local>>,
-}
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
 
 pub struct Pacman {
     alpm: Alpm,
+    /// Handle on the sync `.files` databases, opened on first use.
+    files_alpm: OnceCell<Option<Alpm>>,
+    refresh_mode: RefreshMode,
+    privileges: Privileges,
 }
 
 impl Pacman {
-    pub fn new() -> error::Result<Self> {
+    /// Open an alpm handle configured from pacman.conf, without refreshing the databases.
+    ///
+    /// With [`RefreshMode::CheckUpdates`] the sync databases are read from a temporary copy.
+    pub fn open(refresh_mode: RefreshMode) -> error::Result<Self> {
         let pacman_conf = Config::new()?;
 
+        let db_path = match refresh_mode {
+            RefreshMode::Sync => PathBuf::from(&pacman_conf.db_path),
+            RefreshMode::CheckUpdates => check_updates_db_path(&pacman_conf)?,
+        };
+
         // Initialize alpm
-        let mut alpm = Alpm::new(pacman_conf.root_dir, pacman_conf.db_path)?;
-        for repos in &pacman_conf.repos {
-            alpm.register_syncdb(repos.name.clone(), SigLevel::USE_DEFAULT)?;
-        }
-
-        // Add servers
-        let sync_dbs = alpm.syncdbs_mut();
-        for repo in pacman_conf.repos {
-            for db in sync_dbs {
-                if db.name() == repo.name {
-                    for server in &repo.servers {
-                        db.add_server(server.clone())?;
-                    }
-                }
+        let mut alpm = Alpm::new(pacman_conf.root_dir.as_str(), &db_path.to_string_lossy())?;
+        configure(&mut alpm, &pacman_conf)?;
+
+        Ok(Self {
+            alpm,
+            files_alpm: OnceCell::new(),
+            refresh_mode,
+            privileges: Privileges::current(),
+        })
+    }
+
+    /// Escalate privileges through `helper` for transactions, when not already running as root.
+    pub fn with_privilege_helper(mut self, helper: PrivilegeHelper) -> Self {
+        self.privileges = Privileges::with_helper(helper);
+        self
+    }
+
+    /// Run a transaction on a new alpm handle, streaming its progress through `events` and
+    /// ending with [`TransactionEvent::Finished`].
+    pub fn run_transaction(
+        refresh_mode: RefreshMode,
+        transaction: &Transaction,
+        events: UnboundedSender<TransactionEvent>,
+    ) {
+        let pacman = match transaction {
+            Transaction::Refresh => Self::open(refresh_mode),
+            _ => Self::open(RefreshMode::Sync),
+        };
+
+        let result = pacman.and_then(|mut pacman| match transaction {
+            Transaction::Refresh | Transaction::RefreshSystem => {
+                pacman.refresh_databases(events.clone())
+            }
+            Transaction::Sync(packages) => {
+                pacman.sync_packages(packages.iter().map(String::as_str), events.clone())
             }
+            Transaction::Remove(packages, mode) => {
+                pacman.remove_packages(packages.iter().map(String::as_str), *mode, events.clone())
+            }
+            Transaction::SystemUpgrade(packages) => {
+                pacman.system_upgrade(packages.iter().map(String::as_str), events.clone())
+            }
+            Transaction::CleanOrphans {
+                remove,
+                mark_explicit,
+            } => pacman.clean_orphans(
+                remove.iter().map(String::as_str),
+                mark_explicit.iter().map(String::as_str),
+                events.clone(),
+            ),
+            Transaction::ReplaceBackup(leftover) => pacman.replace_backup(leftover, &events),
+            Transaction::DeleteLeftover(leftover) => pacman.delete_leftover(leftover, &events),
+        });
+
+        _ = events.send(TransactionEvent::Finished(
+            result.map_err(|err| err.to_string()),
+        ));
+    }
+
+    /// Run `f` on a handle using the system databases, which transactions always run against.
+    fn with_system_databases<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> error::Result<T>,
+    ) -> error::Result<T> {
+        match self.refresh_mode {
+            RefreshMode::Sync => f(self),
+            RefreshMode::CheckUpdates => f(&mut Self::open(RefreshMode::Sync)?),
         }
+    }
 
-        // Update packages
-        Command::new("pacman").arg("-Sy").status()?;
+    /// Packages owning the file `query`, with the file lists of installed packages taken from
+    /// the local database only.
+    fn owners(&self, query: &str) -> Vec<FileOwner> {
+        let query = resolve_query(Path::new(self.alpm.root()), query);
+        let local_db = self.alpm.localdb();
+        let mut owners: Vec<FileOwner> = local_db
+            .pkgs()
+            .iter()
+            .flat_map(|pkg| owned_files(pkg, None, &query))
+            .collect();
 
-        Ok(Self { alpm })
+        if let Some(files_alpm) = self.files_handle() {
+            owners.extend(
+                files_alpm
+                    .syncdbs()
+                    .iter()
+                    .flat_map(|db| db.pkgs().iter().map(move |pkg| (db.name(), pkg)))
+                    .filter(|(_, pkg)| local_db.pkg(pkg.name()).is_err())
+                    .flat_map(|(repository, pkg)| owned_files(pkg, Some(repository), &query)),
+            );
+        }
+
+        owners
     }
 
-    pub fn packages(&self) -> impl Iterator<Item = PackageData> {
-        self.alpm.localdb().pkgs().iter().map(|pkg| {
-            let mut install_date: Option<DateTime<Local>> = None;
-            if let Some(install_timestamp) = pkg.install_date() {
-                if let Some(install_datetime_utc) = DateTime::from_timestamp(install_timestamp, 0) {
-                    install_date = Some(Local.from_utc_datetime(&install_datetime_utc.naive_utc()));
-                }
+    /// Handle on the sync `.files` databases, `None` if it cannot be opened.
+    fn files_handle(&self) -> Option<&Alpm> {
+        self.files_alpm
+            .get_or_init(|| self.open_files_handle().ok())
+            .as_ref()
+    }
+
+    /// Open an alpm handle on the sync `.files` databases, like `pacman -F` does.
+    fn open_files_handle(&self) -> error::Result<Alpm> {
+        let mut alpm = Alpm::new(self.alpm.root(), self.alpm.dbpath())?;
+        alpm.set_dbext(".files");
+
+        for db in self.alpm.syncdbs() {
+            alpm.register_syncdb(db.name(), db.siglevel())?;
+        }
+
+        Ok(alpm)
+    }
+
+    /// Installed package with its sync counterpart, if any.
+    fn local_package_data(&self, pkg: &Package) -> PackageData {
+        let sync_pkg = self
+            .alpm
+            .syncdbs()
+            .iter()
+            .find_map(|db| db.pkg(pkg.name()).ok());
+        let new_version = sync_pkg
+            .map(|sync_pkg| sync_pkg.version().as_str())
+            .filter(|version| is_newer(version, pkg.version()));
+
+        PackageData {
+            new_version: new_version.map(String::from),
+            repository: sync_pkg
+                .and_then(|sync_pkg| sync_pkg.db())
+                .map(|db| db.name().to_string()),
+            installed: true,
+            ..installed_package_data(pkg)
+        }
+    }
+
+    /// Sync package with its installed counterpart, if any.
+    fn sync_package_data(&self, pkg: &Package) -> PackageData {
+        let local_pkg = self.alpm.localdb().pkg(pkg.name()).ok();
+        let new_version = local_pkg
+            .filter(|local_pkg| is_newer(pkg.version(), local_pkg.version()))
+            .map(|_| pkg.version().to_string());
+        let local_data = local_pkg.map(installed_package_data).unwrap_or_default();
+
+        PackageData {
+            version: local_pkg
+                .map_or(pkg.version(), |local_pkg| local_pkg.version())
+                .to_string(),
+            new_version,
+            repository: pkg.db().map(|db| db.name().to_string()),
+            installed: local_pkg.is_some(),
+            install_date: local_pkg.and_then(install_date),
+            explicit: local_data.explicit,
+            required_by: local_data.required_by,
+            optional_for: local_data.optional_for,
+            ..package_data(pkg)
+        }
+    }
+
+    /// Download fresh copies of the sync databases, like `pacman -Sy`.
+    ///
+    /// Progress is streamed through `events` while the databases are downloaded.
+    pub fn refresh_databases(
+        &mut self,
+        events: UnboundedSender<TransactionEvent>,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.set_callbacks(events);
+        self.alpm
+            .syncdbs_mut()
+            .update(false)
+            .map_err(Error::RefreshError)?;
+
+        Ok(Vec::new())
+    }
+
+    /// Install or upgrade packages from the sync databases, skipping the ones already up to date.
+    ///
+    /// Progress is streamed through `events` while the transaction runs.
+    pub fn sync_packages<'a>(
+        &mut self,
+        packages: impl IntoIterator<Item = &'a str>,
+        events: UnboundedSender<TransactionEvent>,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.set_callbacks(events);
+        self.alpm
+            .trans_init(TransFlag::NEEDED)
+            .map_err(Error::TransactionError)?;
+
+        let result = self
+            .add_sync_packages(packages)
+            .and_then(|_| self.commit_transaction());
+
+        // A failed release must not hide why the transaction failed
+        let released = self.alpm.trans_release().map_err(Error::TransactionError);
+        let packages = result?;
+        released?;
+
+        Ok(packages)
+    }
+
+    /// Upgrade every installed package, applying replacements, and install `packages` along,
+    /// like `pacman -Su packages`.
+    ///
+    /// Progress is streamed through `events` while the transaction runs.
+    pub fn system_upgrade<'a>(
+        &mut self,
+        packages: impl IntoIterator<Item = &'a str>,
+        events: UnboundedSender<TransactionEvent>,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.set_callbacks(events);
+        self.alpm
+            .trans_init(TransFlag::NEEDED)
+            .map_err(Error::TransactionError)?;
+
+        let result = self
+            .add_sync_packages(packages)
+            .and_then(|_| {
+                self.alpm
+                    .sync_sysupgrade(false)
+                    .map_err(Error::TransactionError)
+            })
+            .and_then(|_| self.commit_transaction());
+
+        let released = self.alpm.trans_release().map_err(Error::TransactionError);
+        let packages = result?;
+        released?;
+
+        Ok(packages)
+    }
+
+    /// Remove installed packages, along with their dependencies depending on `mode`.
+    ///
+    /// Progress is streamed through `events` while the transaction runs.
+    pub fn remove_packages<'a>(
+        &mut self,
+        packages: impl IntoIterator<Item = &'a str>,
+        mode: RemoveMode,
+        events: UnboundedSender<TransactionEvent>,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.set_callbacks(events);
+        self.alpm
+            .trans_init(remove_flags(mode))
+            .map_err(Error::TransactionError)?;
+
+        let result = self
+            .add_remove_packages(packages)
+            .and_then(|_| self.commit_transaction());
+
+        let released = self.alpm.trans_release().map_err(Error::TransactionError);
+        let packages = result?;
+        released?;
+
+        Ok(packages)
+    }
+
+    /// Remove the orphans in `packages`, then mark `mark_explicit` as explicitly installed, like
+    /// `pacman -D --asexplicit`.
+    pub fn clean_orphans<'a>(
+        &mut self,
+        packages: impl IntoIterator<Item = &'a str>,
+        mark_explicit: impl IntoIterator<Item = &'a str>,
+        events: UnboundedSender<TransactionEvent>,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.set_callbacks(events.clone());
+        // The transaction holds the database lock changing install reasons needs
+        self.alpm
+            .trans_init(TransFlag::NONE)
+            .map_err(Error::TransactionError)?;
+
+        // Install reasons are written right away, so only once the removal went through
+        let result = self.add_remove_packages(packages).and_then(|_| {
+            let removed = self.commit_transaction()?;
+            self.mark_explicit(mark_explicit, &events)?;
+
+            Ok(removed)
+        });
+
+        let released = self.alpm.trans_release().map_err(Error::TransactionError);
+        let packages = result?;
+        released?;
+
+        Ok(packages)
+    }
+
+    /// Move the `.pacnew` or `.pacsave` file `leftover` over the backup file it was left next to.
+    pub fn replace_backup(
+        &self,
+        leftover: &str,
+        events: &UnboundedSender<TransactionEvent>,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        let path = self.leftover_backup(leftover)?;
+        let root = Path::new(self.alpm.root());
+
+        fs::rename(root.join(leftover), root.join(path))
+            .map_err(|err| Error::LeftoverError(leftover.to_string(), err.to_string()))?;
+
+        _ = events.send(TransactionEvent::Log(format!(
+            "replaced /{path} with /{leftover}"
+        )));
+
+        Ok(Vec::new())
+    }
+
+    /// Delete the `.pacnew` or `.pacsave` file `leftover`.
+    pub fn delete_leftover(
+        &self,
+        leftover: &str,
+        events: &UnboundedSender<TransactionEvent>,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.leftover_backup(leftover)?;
+
+        fs::remove_file(Path::new(self.alpm.root()).join(leftover))
+            .map_err(|err| Error::LeftoverError(leftover.to_string(), err.to_string()))?;
+
+        _ = events.send(TransactionEvent::Log(format!("deleted /{leftover}")));
+
+        Ok(Vec::new())
+    }
+
+    /// Backup file `leftover` was left next to, as listed by `backup_files`.
+    ///
+    /// Leftovers come from the unprivileged app, so any other path is refused before acting
+    /// on it as root.
+    fn leftover_backup<'a>(&self, leftover: &'a str) -> error::Result<&'a str> {
+        let path = leftover_origin(leftover).ok_or_else(|| not_a_leftover(leftover))?;
+
+        let is_backup = self
+            .alpm
+            .localdb()
+            .pkgs()
+            .iter()
+            .any(|pkg| pkg.backup().iter().any(|backup| backup.name() == path));
+
+        if !is_backup
+            || !leftovers(Path::new(self.alpm.root()), path, &mut HashMap::new())
+                .iter()
+                .any(|known| known == leftover)
+        {
+            return Err(not_a_leftover(leftover));
+        }
+
+        Ok(path)
+    }
+
+    fn mark_explicit<'a>(
+        &self,
+        packages: impl IntoIterator<Item = &'a str>,
+        events: &UnboundedSender<TransactionEvent>,
+    ) -> error::Result<()> {
+        for name in packages {
+            self.alpm
+                .localdb()
+                .pkg(name)
+                .map_err(|_| Error::PackageNotInstalled(name.to_string()))?
+                .set_reason(PackageReason::Explicit)
+                .map_err(Error::TransactionError)?;
+
+            _ = events.send(TransactionEvent::Log(format!(
+                "{name}: install reason has been set to 'explicitly installed'"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Forward alpm logs, events, downloads and progress to `events`.
+    fn set_callbacks(&self, events: UnboundedSender<TransactionEvent>) {
+        self.alpm
+            .set_log_cb(events.clone(), |level, message, events| {
+                let prefix = match level {
+                    LogLevel::ERROR => "error",
+                    LogLevel::WARNING => "warning",
+                    _ => return,
+                };
+
+                _ = events.send(TransactionEvent::Log(format!(
+                    "{prefix}: {}",
+                    message.trim_end()
+                )));
+            });
+
+        self.alpm.set_dl_cb(events.clone(), |file, event, events| {
+            let (downloaded, total) = match event.event() {
+                DownloadEvent::Progress(progress) => (progress.downloaded, progress.total),
+                DownloadEvent::Completed(completed) => (completed.total, completed.total),
+                _ => return,
+            };
+
+            _ = events.send(TransactionEvent::Download {
+                file: file.to_string(),
+                downloaded,
+                total,
+            });
+        });
+
+        self.alpm.set_event_cb(events.clone(), |event, events| {
+            if let Some(message) = event_message(event.event()) {
+                _ = events.send(TransactionEvent::Log(message));
             }
+        });
+
+        self.alpm.set_progress_cb(
+            events,
+            |progress, package, percent, total, current, events| {
+                let operation = match progress {
+                    Progress::AddStart => "installing",
+                    Progress::UpgradeStart => "upgrading",
+                    Progress::DowngradeStart => "downgrading",
+                    Progress::ReinstallStart => "reinstalling",
+                    Progress::RemoveStart => "removing",
+                    Progress::ConflictsStart => "checking for file conflicts",
+                    Progress::DiskspaceStart => "checking available disk space",
+                    Progress::IntegrityStart => "checking package integrity",
+                    Progress::LoadStart => "loading package files",
+                    Progress::KeyringStart => "checking keys in keyring",
+                };
+
+                _ = events.send(TransactionEvent::Progress {
+                    operation: operation.to_string(),
+                    package: package.to_string(),
+                    percent,
+                    current,
+                    total,
+                });
+            },
+        );
+    }
+
+    fn add_sync_packages<'a>(
+        &self,
+        packages: impl IntoIterator<Item = &'a str>,
+    ) -> error::Result<()> {
+        for name in packages {
+            let sync_dbs = self.alpm.syncdbs();
+            let pkg = sync_dbs
+                .iter()
+                .find_map(|db| db.pkg(name).ok())
+                .or_else(|| sync_dbs.find_satisfier(name))
+                .ok_or_else(|| Error::PackageNotFound(name.to_string()))?;
+
+            self.alpm
+                .trans_add_pkg(pkg)
+                .map_err(|err| Error::TransactionError(err.error))?;
+        }
+
+        Ok(())
+    }
+
+    fn add_remove_packages<'a>(
+        &self,
+        packages: impl IntoIterator<Item = &'a str>,
+    ) -> error::Result<()> {
+        for name in packages {
+            let pkg = self
+                .alpm
+                .localdb()
+                .pkg(name)
+                .map_err(|_| Error::PackageNotInstalled(name.to_string()))?;
+
+            self.alpm
+                .trans_remove_pkg(pkg)
+                .map_err(Error::TransactionError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Prepare the current transaction and list the packages it affects.
+    fn prepare_transaction(&mut self) -> error::Result<Vec<TransactionPackage>> {
+        if let Err(err) = self.alpm.trans_prepare() {
+            return Err(match err.try_data() {
+                Some(PrepareData::UnsatisfiedDeps(missing)) => Error::UnsatisfiedDependencies(
+                    missing
+                        .iter()
+                        .map(|miss| format!("{} requires {}", miss.target(), miss.depend()))
+                        .collect(),
+                ),
+                Some(PrepareData::ConflictingDeps(conflicts)) => Error::ConflictingPackages(
+                    conflicts
+                        .iter()
+                        .map(|conflict| {
+                            format!(
+                                "{} and {} are in conflict",
+                                conflict.package1().name(),
+                                conflict.package2().name()
+                            )
+                        })
+                        .collect(),
+                ),
+                Some(PrepareData::PkgInvalidArch(pkgs)) => Error::InvalidArchitecture(
+                    pkgs.iter().map(|pkg| pkg.name().to_string()).collect(),
+                ),
+                None => Error::TransactionError(err.error()),
+            });
+        }
+
+        let local_db = self.alpm.localdb();
+        let mut packages: Vec<TransactionPackage> = self
+            .alpm
+            .trans_add()
+            .iter()
+            .map(|pkg| {
+                let old_pkg = local_db.pkg(pkg.name()).ok();
+
+                TransactionPackage {
+                    name: pkg.name().to_string(),
+                    old_version: old_pkg.map(|old| old.version().to_string()),
+                    new_version: Some(pkg.version().to_string()),
+                    old_size: old_pkg.map_or(0, |old| old.isize()),
+                    new_size: pkg.isize(),
+                    download_size: pkg.download_size(),
+                }
+            })
+            .collect();
+        packages.extend(
+            self.alpm
+                .trans_remove()
+                .iter()
+                .map(|pkg| TransactionPackage {
+                    name: pkg.name().to_string(),
+                    old_version: Some(pkg.version().to_string()),
+                    new_version: None,
+                    old_size: pkg.isize(),
+                    new_size: 0,
+                    download_size: 0,
+                }),
+        );
+
+        Ok(packages)
+    }
+
+    /// Prepare and commit the current transaction.
+    fn commit_transaction(&mut self) -> error::Result<Vec<TransactionPackage>> {
+        let packages = self.prepare_transaction()?;
+
+        // Nothing to do, everything is up to date
+        if packages.is_empty() {
+            return Ok(packages);
+        }
+
+        if let Err(err) = self.alpm.trans_commit() {
+            return Err(match err.try_data() {
+                Some(CommitData::FileConflict(conflicts)) => Error::FileConflicts(
+                    conflicts
+                        .iter()
+                        .map(|conflict| match conflict.conflicting_target() {
+                            Some(target) => format!(
+                                "{}: {} is owned by {}",
+                                conflict.target(),
+                                conflict.file(),
+                                target
+                            ),
+                            None => format!(
+                                "{}: {} exists in filesystem",
+                                conflict.target(),
+                                conflict.file()
+                            ),
+                        })
+                        .collect(),
+                ),
+                Some(CommitData::PkgInvalid(files)) => {
+                    Error::InvalidPackages(files.iter().map(String::from).collect())
+                }
+                None => Error::TransactionError(err.error()),
+            });
+        }
+
+        Ok(packages)
+    }
+}
 
-            let new_version = self
+impl PackageBackend for Pacman {
+    fn packages(&self) -> Box<dyn Iterator<Item = PackageData> + '_> {
+        Box::new(
+            self.alpm
+                .localdb()
+                .pkgs()
+                .iter()
+                .map(|pkg| self.local_package_data(pkg)),
+        )
+    }
+
+    fn repository_packages(&self) -> Box<dyn Iterator<Item = PackageData> + '_> {
+        Box::new(
+            self.alpm
+                .syncdbs()
+                .iter()
+                .flat_map(|db| db.pkgs().iter())
+                .map(|pkg| self.sync_package_data(pkg)),
+        )
+    }
+
+    fn package(&self, name: &str) -> Option<PackageData> {
+        match self.alpm.localdb().pkg(name) {
+            Ok(pkg) => Some(self.local_package_data(pkg)),
+            Err(_) => self
                 .alpm
                 .syncdbs()
                 .iter()
-                .find_map(|db| db.pkg(pkg.name()).ok())
-                .and_then(|sync_pkg| {
-                    if vercmp(pkg.version().to_string(), sync_pkg.version().to_string())
-                        == Ordering::Less
-                    {
-                        Some(sync_pkg.version())
-                    } else {
-                        None
-                    }
-                });
+                .find_map(|db| db.pkg(name).ok())
+                .map(|pkg| self.sync_package_data(pkg)),
+        }
+    }
+
+    fn satisfier(&self, dep: &str) -> Option<PackageData> {
+        match self.alpm.localdb().pkgs().find_satisfier(dep) {
+            Some(pkg) => Some(self.local_package_data(pkg)),
+            None => self
+                .alpm
+                .syncdbs()
+                .find_satisfier(dep)
+                .map(|pkg| self.sync_package_data(pkg)),
+        }
+    }
+
+    fn files(&self, name: &str) -> Option<Vec<PackageFile>> {
+        let root = Path::new(self.alpm.root());
 
-            PackageData {
-                name: pkg.name(),
-                version: pkg.version(),
-                new_version,
-                description: pkg.desc(),
-                architecture: pkg.arch(),
-                url: pkg.url(),
-                licenses: pkg.licenses().into_iter().map(String::from).collect(),
-                provides: pkg.provides(),
-                dependencies: pkg.depends(),
-                optional_dependencies: pkg.optdepends(),
-                conflicts: pkg.conflicts(),
-                replaces: pkg.replaces(),
-                size: to_human_bytes(pkg.isize() as i32),
-                packager: pkg.packager(),
-                install_date,
+        if let Ok(pkg) = self.alpm.localdb().pkg(name) {
+            return Some(package_files(pkg, root));
+        }
+
+        self.files_handle()?
+            .syncdbs()
+            .iter()
+            .find_map(|db| db.pkg(name).ok())
+            .map(|pkg| package_files(pkg, root))
+    }
+
+    /// The lookup opens its own handle, as reading the sync `.files` databases takes a while.
+    fn spawn_owner_lookup(&self, query: String) -> UnboundedReceiver<Vec<FileOwner>> {
+        let (sender, receiver) = unbounded_channel();
+        let refresh_mode = self.refresh_mode;
+
+        thread::spawn(move || {
+            let owners = Self::open(refresh_mode)
+                .map(|pacman| pacman.owners(&query))
+                .unwrap_or_default();
+
+            _ = sender.send(owners);
+        });
+
+        receiver
+    }
+
+    fn spawn_integrity_check(&self, packages: Vec<String>) -> UnboundedReceiver<IntegrityEvent> {
+        let local_db_path = Path::new(self.alpm.dbpath()).join("local");
+
+        let targets = self
+            .alpm
+            .localdb()
+            .pkgs()
+            .iter()
+            .filter(|pkg| packages.is_empty() || packages.iter().any(|name| name == pkg.name()))
+            .map(|pkg| MtreeTarget {
+                package: pkg.name().to_string(),
+                mtree: local_db_path
+                    .join(format!("{}-{}", pkg.name(), pkg.version()))
+                    .join("mtree"),
+                backup: pkg
+                    .backup()
+                    .iter()
+                    .map(|backup| backup.name().to_string())
+                    .collect(),
+            })
+            .collect();
+
+        mtree::spawn_check(PathBuf::from(self.alpm.root()), targets)
+    }
+
+    /// Backup files are compared to the MD5 checksums recorded in the local database, and only
+    /// their directories are searched for leftovers.
+    fn backup_files(&self) -> Vec<BackupFile> {
+        let root = Path::new(self.alpm.root());
+        let mut listings = HashMap::new();
+
+        self.alpm
+            .localdb()
+            .pkgs()
+            .iter()
+            .flat_map(|pkg| pkg.backup().iter().map(move |backup| (pkg.name(), backup)))
+            .map(|(package, backup)| {
+                let full_path = root.join(backup.name());
+
+                BackupFile {
+                    package: package.to_string(),
+                    path: backup.name().to_string(),
+                    status: match compute_md5sum(full_path.as_os_str().as_bytes()) {
+                        Ok(actual) if actual == backup.hash() => BackupStatus::Unmodified,
+                        Ok(_) => BackupStatus::Modified,
+                        Err(_) => match fs::symlink_metadata(&full_path) {
+                            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                                BackupStatus::Missing
+                            }
+                            _ => BackupStatus::Unreadable,
+                        },
+                    },
+                    leftovers: leftovers(root, backup.name(), &mut listings),
+                }
+            })
+            .collect()
+    }
+
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(Path::new(self.alpm.root()).join(path))
+    }
+
+    /// `$DIFFPROG` defaults to `vim -d`, like `pacdiff`, and runs through the privilege helper
+    /// when not root.
+    fn merge_command(&self, path: &str, leftover: &str) -> error::Result<Command> {
+        let diffprog = env::var("DIFFPROG").unwrap_or_else(|_| "vim -d".to_string());
+        let mut words = diffprog.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| Error::MergeUnavailable("DIFFPROG is empty".to_string()))?;
+
+        let mut command = match self.privileges {
+            Privileges::Root => Command::new(program),
+            Privileges::Escalate(helper) => {
+                let mut command = Command::new(helper.to_string());
+                command.arg(program);
+                command
             }
+            Privileges::ReadOnly(_) => {
+                return Err(Error::MergeUnavailable(
+                    self.privileges.read_only_reason().unwrap_or_default(),
+                ));
+            }
+        };
+
+        let root = Path::new(self.alpm.root());
+        command
+            .args(words)
+            .arg(root.join(path))
+            .arg(root.join(leftover));
+
+        Ok(command)
+    }
+
+    /// The log is read from the `LogFile` set in pacman.conf.
+    fn history(&self) -> error::Result<Vec<HistoryTransaction>> {
+        let path = self
+            .alpm
+            .logfile()
+            .ok_or_else(|| Error::HistoryError("no LogFile is set".to_string()))?;
+        let content =
+            fs::read(path).map_err(|err| Error::HistoryError(format!("{path}: {err}")))?;
+
+        Ok(history::parse(&String::from_utf8_lossy(&content)))
+    }
+
+    /// Reopen the alpm handle so changes made by another handle are picked up.
+    fn reload(&mut self) -> error::Result<()> {
+        *self = Self {
+            privileges: self.privileges,
+            ..Self::open(self.refresh_mode)?
+        };
+
+        Ok(())
+    }
+
+    fn partial_upgrade(&self, packages: &HashSet<String>) -> PartialUpgrade {
+        let local_db = self.alpm.localdb();
+        let sync_dbs = self.alpm.syncdbs();
+
+        let upgradables: Vec<&str> = local_db
+            .pkgs()
+            .iter()
+            .filter(|pkg| pkg.sync_new_version(sync_dbs).is_some())
+            .map(|pkg| pkg.name())
+            .collect();
+
+        let mut partial_upgrade = PartialUpgrade::default();
+
+        if upgradables.iter().any(|name| packages.contains(*name)) {
+            partial_upgrade.skipped_upgrades = upgradables
+                .iter()
+                .filter(|name| !packages.contains(**name))
+                .map(|name| name.to_string())
+                .collect();
+        }
+
+        for name in packages {
+            let Some(sync_pkg) = sync_dbs.iter().find_map(|db| db.pkg(name.as_str()).ok()) else {
+                continue;
+            };
+
+            for dep in sync_pkg.depends() {
+                // Looked up by name, as an installed version too old for the dependency
+                // satisfies nothing
+                let Some(local_pkg) = local_db.pkgs().find_satisfier(dep.name()) else {
+                    continue;
+                };
+
+                let outdated = upgradables.contains(&local_pkg.name())
+                    || local_db.pkgs().find_satisfier(dep.to_string()).is_none();
+
+                if outdated && !packages.contains(local_pkg.name()) {
+                    partial_upgrade
+                        .outdated_dependencies
+                        .push((local_pkg.name().to_string(), name.clone()));
+                }
+            }
+        }
+
+        partial_upgrade
+    }
+
+    /// Only [`RefreshMode::CheckUpdates`] reads the list from other databases than the system
+    /// ones.
+    fn stale_upgrades(&self) -> Vec<String> {
+        if self.refresh_mode == RefreshMode::Sync {
+            return Vec::new();
+        }
+
+        let Ok(system) = Self::open(RefreshMode::Sync) else {
+            return Vec::new();
+        };
+        let system_dbs = system.alpm.syncdbs();
+        let sync_dbs = self.alpm.syncdbs();
+
+        self.alpm
+            .localdb()
+            .pkgs()
+            .iter()
+            .filter_map(|pkg| {
+                let version = pkg.sync_new_version(sync_dbs)?.version();
+                let known = system
+                    .alpm
+                    .localdb()
+                    .pkg(pkg.name())
+                    .ok()
+                    .and_then(|pkg| pkg.sync_new_version(system_dbs))
+                    .is_some_and(|system_pkg| !is_newer(version, system_pkg.version()));
+
+                (!known).then(|| pkg.name().to_string())
+            })
+            .collect()
+    }
+
+    /// The transaction is only prepared, without locking the database.
+    fn system_upgrade_preview(
+        &mut self,
+        packages: &[String],
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.with_system_databases(|pacman| {
+            pacman
+                .alpm
+                .trans_init(TransFlag::NEEDED | TransFlag::NO_LOCK)
+                .map_err(Error::TransactionError)?;
+
+            let result = pacman
+                .add_sync_packages(packages.iter().map(String::as_str))
+                .and_then(|_| {
+                    pacman
+                        .alpm
+                        .sync_sysupgrade(false)
+                        .map_err(Error::TransactionError)
+                })
+                .and_then(|_| pacman.prepare_transaction());
+
+            let released = pacman.alpm.trans_release().map_err(Error::TransactionError);
+            let packages = result?;
+            released?;
+
+            Ok(packages)
         })
     }
+
+    /// The transaction is only prepared, without locking the database.
+    fn removal_preview(
+        &mut self,
+        packages: &[String],
+        mode: RemoveMode,
+    ) -> error::Result<Vec<TransactionPackage>> {
+        self.with_system_databases(|pacman| {
+            pacman
+                .alpm
+                .trans_init(remove_flags(mode) | TransFlag::NO_LOCK)
+                .map_err(Error::TransactionError)?;
+
+            let result = pacman
+                .add_remove_packages(packages.iter().map(String::as_str))
+                .and_then(|_| pacman.prepare_transaction());
+
+            let released = pacman.alpm.trans_release().map_err(Error::TransactionError);
+            let packages = result?;
+            released?;
+
+            Ok(packages)
+        })
+    }
+
+    fn privileges(&self) -> Privileges {
+        self.privileges
+    }
+
+    /// Refreshing the temporary databases of [`RefreshMode::CheckUpdates`] needs no rights.
+    fn requires_root(&self, transaction: &Transaction) -> bool {
+        !matches!(
+            (transaction, self.refresh_mode),
+            (Transaction::Refresh, RefreshMode::CheckUpdates)
+        )
+    }
+
+    /// The transaction runs on its own thread and alpm handle, or in a privileged helper
+    /// process when escalating privileges.
+    fn spawn_transaction(&self, transaction: Transaction) -> UnboundedReceiver<TransactionEvent> {
+        if self.requires_root(&transaction)
+            && let Privileges::Escalate(helper) = self.privileges
+        {
+            return privilege::spawn_helper(helper, &transaction);
+        }
+
+        let (sender, receiver) = unbounded_channel();
+        let refresh_mode = self.refresh_mode;
+
+        thread::spawn(move || Self::run_transaction(refresh_mode, &transaction, sender));
+
+        receiver
+    }
+}
+
+/// Fields shared by installed and sync packages.
+fn package_data(pkg: &Package) -> PackageData {
+    PackageData {
+        name: pkg.name().to_string(),
+        version: pkg.version().to_string(),
+        new_version: None,
+        description: pkg.desc().map(String::from),
+        architecture: pkg.arch().map(String::from),
+        url: pkg.url().map(String::from),
+        repository: None,
+        installed: false,
+        explicit: false,
+        groups: pkg.groups().into_iter().map(String::from).collect(),
+        licenses: pkg.licenses().into_iter().map(String::from).collect(),
+        provides: dep_strings(pkg.provides()),
+        dependencies: dep_strings(pkg.depends()),
+        optional_dependencies: dep_strings(pkg.optdepends()),
+        conflicts: dep_strings(pkg.conflicts()),
+        replaces: dep_strings(pkg.replaces()),
+        required_by: Vec::new(),
+        optional_for: Vec::new(),
+        size: pkg.isize(),
+        packager: pkg.packager().map(String::from),
+        install_date: install_date(pkg),
+        build_date: local_datetime(pkg.build_date()),
+    }
+}
+
+/// Files of `pkg` with their size on disk under `root`.
+fn package_files(pkg: &Package, root: &Path) -> Vec<PackageFile> {
+    pkg.files()
+        .files()
+        .iter()
+        .map(|file| PackageFile {
+            path: file.name().to_string(),
+            size: file.size(),
+            disk_size: fs::symlink_metadata(root.join(file.name()))
+                .ok()
+                .map(|metadata| metadata.len()),
+        })
+        .collect()
+}
+
+/// Files of `pkg` matching `query`, with the package as their owner.
+fn owned_files(pkg: &Package, repository: Option<&str>, query: &str) -> Vec<FileOwner> {
+    pkg.files()
+        .files()
+        .iter()
+        .filter(|file| file_matches(file.name(), query))
+        .map(|file| FileOwner {
+            package: pkg.name().to_string(),
+            repository: repository.map(String::from),
+            path: file.name().to_string(),
+        })
+        .collect()
+}
+
+/// `.pacnew` and `.pacsave` files left next to the backup file `path`, relative to `root`.
+///
+/// `listings` caches the content of the directories already read.
+fn leftovers(root: &Path, path: &str, listings: &mut HashMap<String, Vec<String>>) -> Vec<String> {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let entries = listings.entry(dir.to_string()).or_insert_with(|| {
+        fs::read_dir(root.join(dir))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect()
+    });
+
+    let mut leftovers: Vec<String> = entries
+        .iter()
+        .filter(|entry| leftover_origin(entry) == Some(name))
+        .map(|entry| format!("{path}{}", &entry[name.len()..]))
+        .collect();
+
+    leftovers.sort();
+    leftovers
+}
+
+fn not_a_leftover(path: &str) -> Error {
+    Error::LeftoverError(
+        path.to_string(),
+        "not a .pacnew or .pacsave file of an installed backup file".to_string(),
+    )
+}
+
+/// Fields of an installed package, with its install reason and dependents.
+fn installed_package_data(pkg: &Package) -> PackageData {
+    PackageData {
+        explicit: pkg.reason() == PackageReason::Explicit,
+        required_by: pkg.required_by().into_iter().collect(),
+        optional_for: pkg.optional_for().into_iter().collect(),
+        ..package_data(pkg)
+    }
+}
+
+fn dep_strings(deps: AlpmList<&Dep>) -> Vec<String> {
+    deps.iter().map(|dep| dep.to_string()).collect()
+}
+
+fn install_date(pkg: &Package) -> Option<DateTime<Local>> {
+    pkg.install_date().and_then(local_datetime)
+}
+
+fn local_datetime(timestamp: i64) -> Option<DateTime<Local>> {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|datetime_utc| Local.from_utc_datetime(&datetime_utc.naive_utc()))
+}
+
+/// Temporary database path sharing the local database, like `checkupdates` uses.
+///
+/// The sync databases start as a copy of the system ones until they are refreshed.
+fn check_updates_db_path(conf: &Config) -> error::Result<PathBuf> {
+    let db_path = env::temp_dir().join(format!("tecarius-db-{}", Uid::current()));
+    let system_db_path = Path::new(&conf.db_path);
+
+    fs::create_dir_all(&db_path)?;
+
+    let local_path = db_path.join("local");
+    if fs::symlink_metadata(&local_path).is_err() {
+        symlink(system_db_path.join("local"), &local_path)?;
+    }
+
+    let sync_path = db_path.join("sync");
+    if !sync_path.exists() {
+        fs::create_dir(&sync_path)?;
+
+        for entry in fs::read_dir(system_db_path.join("sync"))? {
+            let entry = entry?;
+            fs::copy(entry.path(), sync_path.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(db_path)
+}
+
+fn remove_flags(mode: RemoveMode) -> TransFlag {
+    match mode {
+        RemoveMode::Packages => TransFlag::NONE,
+        RemoveMode::Recursive => TransFlag::RECURSE,
+        RemoveMode::RecursiveNoSave => TransFlag::RECURSE | TransFlag::NO_SAVE,
+    }
+}
+
+/// Describe an alpm event as a log line, ignoring the ones not worth showing.
+fn event_message(event: Event) -> Option<String> {
+    let message = match event {
+        Event::CheckDepsStart => "checking dependencies...".to_string(),
+        Event::ResolveDepsStart => "resolving dependencies...".to_string(),
+        Event::InterConflictsStart => "looking for conflicting packages...".to_string(),
+        Event::FileConflictsStart => "checking for file conflicts...".to_string(),
+        Event::IntegrityStart => "checking package integrity...".to_string(),
+        Event::KeyringStart => "checking keys in keyring...".to_string(),
+        Event::KeyDownloadStart => "downloading required keys...".to_string(),
+        Event::LoadStart => "loading package files...".to_string(),
+        Event::DiskSpaceStart => "checking available disk space...".to_string(),
+        Event::TransactionStart => "processing package changes...".to_string(),
+        Event::RetrieveStart => "synchronizing package databases...".to_string(),
+        Event::RetrieveFailed => "failed to synchronize package databases".to_string(),
+        Event::PkgRetrieveStart(retrieve) => format!(
+            "retrieving {} packages ({})...",
+            retrieve.num(),
+            to_human_bytes(retrieve.total_size() as f64)
+        ),
+        Event::PkgRetrieveFailed(_) => "failed to retrieve some packages".to_string(),
+        Event::PackageOperationDone(operation) => match operation.operation() {
+            PackageOperation::Install(new) => {
+                format!("installed {} ({})", new.name(), new.version())
+            }
+            PackageOperation::Upgrade(new, old) => format!(
+                "upgraded {} ({} -> {})",
+                new.name(),
+                old.version(),
+                new.version()
+            ),
+            PackageOperation::Reinstall(new, _) => {
+                format!("reinstalled {} ({})", new.name(), new.version())
+            }
+            PackageOperation::Downgrade(new, old) => format!(
+                "downgraded {} ({} -> {})",
+                new.name(),
+                old.version(),
+                new.version()
+            ),
+            PackageOperation::Remove(old) => {
+                format!("removed {} ({})", old.name(), old.version())
+            }
+        },
+        Event::ScriptletInfo(info) => info.line().trim_end().to_string(),
+        Event::OptDepRemoval(removal) => format!(
+            "{} optionally requires {}",
+            removal.pkg().name(),
+            removal.optdep()
+        ),
+        Event::DatabaseMissing(missing) => {
+            format!("database file for '{}' does not exist", missing.dbname())
+        }
+        Event::PacnewCreated(pacnew) => format!(
+            "warning: {} installed as {}.pacnew",
+            pacnew.file(),
+            pacnew.file()
+        ),
+        Event::PacsaveCreated(pacsave) => format!(
+            "warning: {} saved as {}.pacsave",
+            pacsave.file(),
+            pacsave.file()
+        ),
+        Event::HookStart(hook) => match hook.when() {
+            HookWhen::PreTransaction => "running pre-transaction hooks...".to_string(),
+            HookWhen::PostTransaction => "running post-transaction hooks...".to_string(),
+        },
+        Event::HookRunStart(hook) => format!(
+            "({}/{}) {}",
+            hook.position(),
+            hook.total(),
+            hook.desc().unwrap_or(hook.name())
+        ),
+        _ => return None,
+    };
+
+    Some(message)
+}
+
+/// Apply pacman.conf options to the alpm handle and register the sync databases.
+fn configure(alpm: &mut Alpm, conf: &Config) -> error::Result<()> {
+    alpm.set_cachedirs(conf.cache_dir.iter())?;
+    alpm.set_hookdirs(conf.hook_dir.iter())?;
+    alpm.set_gpgdir(conf.gpg_dir.as_str())?;
+    alpm.set_logfile(conf.log_file.as_str())?;
+    alpm.set_ignorepkgs(conf.ignore_pkg.iter())?;
+    alpm.set_ignoregroups(conf.ignore_group.iter())?;
+    alpm.set_noupgrades(conf.no_upgrade.iter())?;
+    alpm.set_noextracts(conf.no_extract.iter())?;
+    alpm.set_architectures(conf.architecture.iter())?;
+    alpm.set_use_syslog(conf.use_syslog);
+    alpm.set_check_space(conf.check_space);
+    alpm.set_disable_dl_timeout(conf.disable_download_timeout);
+    alpm.set_disable_sandbox(conf.disable_sandbox);
+    alpm.set_parallel_downloads(conf.parallel_downloads.max(1) as u32);
+
+    let default_sig_level = sig_level(&conf.sig_level, DEFAULT_SIG_LEVEL);
+    alpm.set_default_siglevel(default_sig_level)?;
+
+    for repo in &conf.repos {
+        let repo_sig_level = if repo.sig_level.is_empty() {
+            SigLevel::USE_DEFAULT
+        } else {
+            sig_level(&repo.sig_level, default_sig_level)
+        };
+
+        let db = alpm.register_syncdb_mut(repo.name.as_str(), repo_sig_level)?;
+        for server in &repo.servers {
+            db.add_server(server.as_str())?;
+        }
+    }
+
+    // Answer questions the same way `pacman --noconfirm` would
+    alpm.set_question_cb((), |question, _| match question.question() {
+        Question::InstallIgnorepkg(mut question) => question.set_install(true),
+        Question::Replace(question) => question.set_replace(true),
+        Question::Conflict(mut question) => question.set_remove(false),
+        Question::Corrupted(mut question) => question.set_remove(true),
+        Question::RemovePkgs(mut question) => question.set_skip(false),
+        Question::SelectProvider(mut question) => question.set_index(0),
+        Question::ImportKey(mut question) => question.set_import(true),
+    });
+
+    Ok(())
 }
 
-pub fn sync_packages<'a>(
-    packages: impl IntoIterator<Item = &'a str>,
-) -> error::Result<ChildStdout> {
-    let mut process = Command::new("pacman")
-        .args(["-S", "--needed", "--noconfirm"])
-        .args(packages)
-        .stdout(Stdio::piped())
-        .spawn()?;
-    let stdout = process.stdout.take().unwrap();
+/// Signature level pacman uses when `SigLevel` is not set.
+const DEFAULT_SIG_LEVEL: SigLevel = SigLevel::PACKAGE
+    .union(SigLevel::PACKAGE_OPTIONAL)
+    .union(SigLevel::DATABASE)
+    .union(SigLevel::DATABASE_OPTIONAL);
+
+/// Parse a pacman.conf `SigLevel` option on top of a base level.
+fn sig_level(options: &[String], base: SigLevel) -> SigLevel {
+    let mut level = base;
+
+    for option in options {
+        let (package, database, value) = if let Some(value) = option.strip_prefix("Package") {
+            (true, false, value)
+        } else if let Some(value) = option.strip_prefix("Database") {
+            (false, true, value)
+        } else {
+            (true, true, option.as_str())
+        };
+
+        let flags = |package_flag: SigLevel, database_flag: SigLevel| {
+            let mut flags = SigLevel::NONE;
+            if package {
+                flags |= package_flag;
+            }
+            if database {
+                flags |= database_flag;
+            }
+            flags
+        };
+
+        let required = flags(SigLevel::PACKAGE, SigLevel::DATABASE);
+        let optional = flags(SigLevel::PACKAGE_OPTIONAL, SigLevel::DATABASE_OPTIONAL);
+        let trust = flags(
+            SigLevel::PACKAGE_MARGINAL_OK | SigLevel::PACKAGE_UNKNOWN_OK,
+            SigLevel::DATABASE_MARGINAL_OK | SigLevel::DATABASE_UNKNOWN_OK,
+        );
+
+        match value {
+            "Never" => level.remove(required),
+            "Optional" => level.insert(required | optional),
+            "Required" => {
+                level.insert(required);
+                level.remove(optional);
+            }
+            "TrustedOnly" => level.remove(trust),
+            "TrustAll" => level.insert(trust),
+            _ => (),
+        }
+    }
 
-    Ok(stdout)
+    level
 }
@@ -0,0 +1,114 @@
+use crate::{
+    backend::{Transaction, TransactionEvent},
+    config::{PrivilegeHelper, RefreshMode},
+    error::{self, Error},
+    pacman::Pacman,
+};
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    process::{Command, Stdio},
+    thread,
+};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// Argument running Tecarius as the privileged helper of a transaction.
+pub const HELPER_ARG: &str = "--helper";
+
+/// Whether the `helper` command can be found in `PATH`.
+pub fn is_installed(helper: PrivilegeHelper) -> bool {
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| dir.join(helper.to_string()).is_file())
+    })
+}
+
+/// Run `transaction` in a copy of Tecarius started as root through `helper`.
+///
+/// The helper may prompt for authentication on the terminal before its first event.
+pub fn spawn_helper(
+    helper: PrivilegeHelper,
+    transaction: &Transaction,
+) -> UnboundedReceiver<TransactionEvent> {
+    let (sender, receiver) = unbounded_channel();
+    let transaction = serde_json::to_string(transaction);
+
+    thread::spawn(move || {
+        let result = transaction
+            .map_err(|err| Error::HelperError(err.to_string()))
+            .and_then(|transaction| forward_helper_events(helper, &transaction, &sender));
+
+        if let Err(err) = result {
+            _ = sender.send(TransactionEvent::Finished(Err(err.to_string())));
+        }
+    });
+
+    receiver
+}
+
+/// Run the helper process and forward the events it prints as JSON lines.
+fn forward_helper_events(
+    helper: PrivilegeHelper,
+    transaction: &str,
+    events: &UnboundedSender<TransactionEvent>,
+) -> error::Result<()> {
+    let mut child = Command::new(helper.to_string())
+        .arg(env::current_exe()?)
+        .arg(HELPER_ARG)
+        .arg(transaction)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let mut finished = false;
+    let mut read_error = None;
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    read_error = Some(err);
+                    break;
+                }
+            };
+
+            // Lines that are not events, like warnings of the helper, are shown as they are
+            let event = serde_json::from_str(&line).unwrap_or(TransactionEvent::Log(line));
+
+            finished |= matches!(event, TransactionEvent::Finished(_));
+            _ = events.send(event);
+        }
+    }
+
+    // Waited for in any case, so the transaction is never reported over while still running
+    let status = child.wait()?;
+
+    if let Some(err) = read_error {
+        return Err(Error::HelperError(format!(
+            "reading {helper} output: {err}"
+        )));
+    }
+
+    if !finished {
+        return Err(Error::HelperError(format!("{helper} exited with {status}")));
+    }
+
+    Ok(())
+}
+
+/// Run a transaction given by the unprivileged app, printing its events as JSON lines.
+pub async fn run_helper(transaction: &str) -> error::Result<()> {
+    let transaction: Transaction =
+        serde_json::from_str(transaction).map_err(|err| Error::HelperError(err.to_string()))?;
+
+    let (sender, mut receiver) = unbounded_channel();
+    thread::spawn(move || Pacman::run_transaction(RefreshMode::Sync, &transaction, sender));
+
+    let mut stdout = io::stdout();
+    while let Some(event) = receiver.recv().await {
+        let line =
+            serde_json::to_string(&event).map_err(|err| Error::HelperError(err.to_string()))?;
+        writeln!(stdout, "{line}")?;
+    }
+
+    Ok(())
+}